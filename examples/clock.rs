@@ -1,4 +1,4 @@
-use std::f32::consts::PI;
+use ray_tracer_challenge::consts::PI;
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -20,13 +20,13 @@ fn main() -> std::io::Result<()> {
 
     // We could simplify this to PI / 6 but this makes it more obvious where
     // the numbers come from
-    let hour_turn = rotation_z(-(2.0 * PI) / HOURS as f32);
+    let hour_turn = rotation_z(-(2.0 * PI) / HOURS as Float);
 
     for _hour in 0..HOURS {
         println!("{:?}", hand);
 
-        let x: i32 = ((CANVAS_WIDTH as f32 * 0.4) * hand.x()) as i32;
-        let y: i32 = ((CANVAS_HEIGHT as f32 * 0.4) * hand.y()) as i32;
+        let x: i32 = ((CANVAS_WIDTH as Float * 0.4) * hand.x()) as i32;
+        let y: i32 = ((CANVAS_HEIGHT as Float * 0.4) * hand.y()) as i32;
 
         println!("Starting here: {}, {}", x, y);
 
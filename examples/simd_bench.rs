@@ -0,0 +1,104 @@
+// Times the matrix math with the SIMD lanes against the plain scalar lanes, and against the
+// row-by-column and cofactor versions they replaced. Vector dot and cross products, which use the
+// same lanes, are timed too.
+//
+//   cargo run --release --example simd_bench [iterations]
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use ray_tracer_challenge::math::simd::{self, scalar, Float4};
+use ray_tracer_challenge::math::transforms::*;
+use ray_tracer_challenge::math::Matrix4x4;
+use ray_tracer_challenge::{Float, Vector};
+
+const DEFAULT_ITERATIONS: u32 = 2_000_000;
+
+fn time<T, F: FnMut() -> T>(name: &str, iterations: u32, mut f: F) {
+    let started = Instant::now();
+
+    for _ in 0..iterations {
+        black_box(f());
+    }
+
+    let nanos = started.elapsed().as_nanos() as f64 / iterations as f64;
+
+    println!("  {:<10} {:8.1} ns", name, nanos);
+}
+
+fn row_by_column(a: &Matrix4x4, b: &Matrix4x4) -> Matrix4x4 {
+    let mut values: [Float; 16] = [0.0; 16];
+
+    for r in 0..4 {
+        for c in 0..4 {
+            values[r * 4 + c] = a.row(r) * b.col(c);
+        }
+    }
+
+    Matrix4x4::new(values)
+}
+
+fn cofactor_inverse(m: &Matrix4x4) -> Matrix4x4 {
+    let mut inverted = Matrix4x4::default();
+
+    for r in 0..4 {
+        for c in 0..4 {
+            inverted[[c, r]] = m.cofactor(r, c) / m.determinant();
+        }
+    }
+
+    inverted
+}
+
+fn main() {
+    let iterations = std::env::args()
+        .nth(1)
+        .map(|n| n.parse().expect("Expected a number of iterations"))
+        .unwrap_or(DEFAULT_ITERATIONS);
+
+    let a = TransformationMatrix::identity()
+        .scale(2.0, 0.5, 3.0)
+        .rotate_y(0.7)
+        .translate(1.0, -2.0, 3.5);
+    let b = TransformationMatrix::identity()
+        .rotate_x(-0.3)
+        .shear(0.0, 0.2, 0.0, 0.0, 0.1, 0.0)
+        .translate(4.0, 0.0, 1.0);
+    let xyzw = [0.5, -1.0, 2.0, 1.0];
+
+    println!("{} iterations each", iterations);
+
+    println!("Matrix4x4 * Matrix4x4");
+    time("before", iterations, || {
+        row_by_column(black_box(&a), black_box(&b))
+    });
+    time("scalar", iterations, || {
+        simd::multiply::<scalar::Float4>(black_box(&a), black_box(&b))
+    });
+    time("simd", iterations, || {
+        simd::multiply::<Float4>(black_box(&a), black_box(&b))
+    });
+
+    println!("Matrix4x4 * point");
+    time("scalar", iterations, || {
+        simd::transform::<scalar::Float4>(black_box(&a), black_box(xyzw))
+    });
+    time("simd", iterations, || {
+        simd::transform::<Float4>(black_box(&a), black_box(xyzw))
+    });
+
+    println!("Matrix4x4::inverse");
+    time("before", iterations, || cofactor_inverse(black_box(&a)));
+    time("scalar", iterations, || {
+        simd::inverse::<scalar::Float4>(black_box(&a))
+    });
+    time("simd", iterations, || {
+        simd::inverse::<Float4>(black_box(&a))
+    });
+
+    let (u, v) = (Vector::new(0.5, -1.0, 2.0), Vector::new(3.0, 0.25, -4.0));
+
+    println!("Vector::dot and Vector::cross");
+    time("dot", iterations, || black_box(u).dot(black_box(v)));
+    time("cross", iterations, || black_box(u).cross(black_box(v)));
+}
@@ -0,0 +1,65 @@
+use ray_tracer_challenge::consts::PI;
+
+use ray_tracer_challenge::animation::*;
+use ray_tracer_challenge::color::Color;
+use ray_tracer_challenge::render::TileRenderer;
+use ray_tracer_challenge::scenes;
+use ray_tracer_challenge::{Float, Vector, STATIONARY};
+
+const WIDTH: Float = 200.0;
+const HEIGHT: Float = 100.0;
+const FRAMES: u32 = 48;
+const OUTPUT: &str = "turntable";
+
+fn main() -> Result<(), AnimationError> {
+    let mut animation = Animation::new(scenes::spheres(WIDTH, HEIGHT, PI / 3.0));
+    let seconds = animation.time_of(FRAMES);
+
+    // One full turn of the room at a steady speed
+    animation.camera.turn.add_key(0.0, 0.0, Easing::Linear);
+    animation
+        .camera
+        .turn
+        .add_key(seconds, 2.0 * PI, Easing::Linear);
+
+    // The middle sphere bobs up and back while blushing red
+    let mut middle = ObjectTrack::new(scenes::SPHERES_MIDDLE);
+    middle
+        .transform
+        .translation
+        .add_key(0.0, STATIONARY, Easing::EaseInOut);
+    middle.transform.translation.add_key(
+        seconds / 2.0,
+        Vector::new(0.0, 0.5, 0.0),
+        Easing::EaseInOut,
+    );
+    middle
+        .transform
+        .translation
+        .add_key(seconds, STATIONARY, Easing::Linear);
+    middle
+        .material
+        .color
+        .add_key(0.0, Color::new(0.1, 1.0, 0.5), Easing::EaseInOut);
+    middle
+        .material
+        .color
+        .add_key(seconds / 2.0, Color::new(1.0, 0.2, 0.2), Easing::EaseInOut);
+    middle
+        .material
+        .color
+        .add_key(seconds, Color::new(0.1, 1.0, 0.5), Easing::Linear);
+    animation.objects.push(middle);
+
+    println!("Rendering {} frames to {}/ ...", FRAMES, OUTPUT);
+
+    let frames = animation.render_frames(1..=FRAMES, OUTPUT, &TileRenderer::new())?;
+
+    println!(
+        "Rendered {} frames, skipped {} already there",
+        frames.rendered.len(),
+        frames.skipped.len()
+    );
+
+    Ok(())
+}
@@ -37,6 +37,23 @@ Scenario: Constructing a ray when the camera is transformed
   Then r.origin = point(0, 2, -5)
     And r.direction = vector(√2/2, 0, -√2/2)
 
+Scenario: A camera can't look along its up vector
+  Given c ← camera(11, 11, π/2)
+    And from ← point(0, 0, -5)
+    And to ← point(0, 0, 0)
+    And up ← vector(0, 0, 1)
+  When try c.transform ← view_transform(from, to, up)
+  Then the transform is rejected
+    And c.transform = identity_matrix
+
+Scenario: A camera can't look from a point to itself
+  Given c ← camera(11, 11, π/2)
+    And from ← point(1, 2, 3)
+    And to ← point(1, 2, 3)
+    And up ← vector(0, 1, 0)
+  When try c.transform ← view_transform(from, to, up)
+  Then the transform is rejected
+
 Scenario: Rendering a world with a camera
   Given w ← default_world()
     And c ← camera(11, 11, π/2)
@@ -56,6 +56,17 @@ Scenario: Changing a sphere's transformation
   When set_transform(s, t)
   Then s.transform = t
 
+Scenario: A sphere can't be scaled flat
+  Given s ← sphere()
+  When try set_transform(s, scaling(2, 0, 2))
+  Then the transform is rejected
+    And s.transform = identity_matrix
+
+Scenario: A sphere can be scaled very thin
+  Given s ← sphere()
+  When try set_transform(s, scaling(10, 0.01, 10))
+  Then the transform is accepted
+
 Scenario: Intersecting a scaled sphere with a ray
   Given r ← ray(point(0, 0, -5), vector(0, 0, 1))
     And s ← sphere()
@@ -32,6 +32,22 @@ Scenario: Multiplying by the inverse of a scaling matrix
     And v ← vector(-4, 6, 8)
    Then inv * v = vector(-2, 2, 2)
 
+Scenario: A scaling by zero can't be inverted
+  Given transform ← scaling(0, 2, 3)
+  Then transform is not invertible
+    And inverse(transform) is nothing
+
+Scenario: A scaling by zero along every axis can't be inverted
+  Given transform ← scaling(0, 0, 0)
+  Then transform is not invertible
+    And inverse(transform) is nothing
+
+Scenario: A very small scaling can still be inverted
+  Given transform ← scaling(0.0078125, 0.0078125, 0.0078125)
+    And inv ← inverse(transform)
+    And v ← vector(0.0078125, 0.015625, 0.0234375)
+  Then inv * v = vector(1, 2, 3)
+
 Scenario: Reflection is scaling by a negative value
   Given transform ← scaling(-1, 1, 1)
     And p ← point(2, 3, 4)
@@ -117,6 +133,16 @@ Scenario: Chained transformations must be applied in reverse order
   When T ← C * B * A
   Then T * p = point(15, 0, 7)
 
+Scenario: Fluent transformations are applied in reading order
+  Given p ← point(1, 0, 1)
+  When T ← identity_matrix.rotate_x(π / 2).scale(5, 5, 5).translate(10, 5, 7)
+  Then T * p = point(15, 0, 7)
+
+Scenario: Rotating a point around an arbitrary axis
+  Given p ← point(1, 0, 0)
+  When T ← rotation(vector(1, 1, 1), 2π / 3)
+  Then T * p = point(0, 1, 0)
+
 Scenario: The transformation matrix for the default orientation
   Given from ← point(0, 0, 0)
     And to ← point(0, 0, -1)
@@ -0,0 +1,710 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use super::color::Color;
+use super::material::Material;
+use super::math::quaternion::Quaternion;
+use super::math::transforms::*;
+use super::render::TileRenderer;
+use super::scenes::Scene;
+use super::{Float, Point, Vector, CENTER_ORIGIN, STATIONARY};
+
+pub const DEFAULT_FRAMES_PER_SECOND: Float = 24.0;
+
+/// How a value moves from one keyframe to the next. `t` runs from 0 at the keyframe to 1 at the
+/// following one.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up
+    EaseIn,
+    /// Starts quickly and slows down
+    EaseOut,
+    /// Slow at both ends
+    EaseInOut,
+    /// Keeps the keyframe's value until the next one
+    Hold,
+}
+
+impl Easing {
+    pub fn apply(&self, t: Float) -> Float {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Hold => 0.0,
+        }
+    }
+}
+
+/// Values a track can blend between
+pub trait Interpolate: Copy {
+    /// `self` when `t` is 0 and `other` when it's 1
+    fn interpolate(&self, other: &Self, t: Float) -> Self;
+}
+
+impl Interpolate for Float {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Point {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Vector {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+/// Turns at a steady speed, the short way round
+impl Interpolate for Quaternion {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// Blends each part separately, so a `Track<Decomposition>` can animate between two transforms
+impl Interpolate for Decomposition {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        Decomposition {
+            translation: self.translation.interpolate(&other.translation, t),
+            rotation: self.rotation.interpolate(&other.rotation, t),
+            scale: self.scale.interpolate(&other.scale, t),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    /// Seconds from the start of the animation
+    pub time: Float,
+    pub value: T,
+    /// Curve toward the next keyframe
+    pub easing: Easing,
+}
+
+/// A value changing over time. Before the first keyframe it has the first keyframe's value and
+/// after the last it keeps the last's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new() -> Self {
+        Self { keyframes: vec![] }
+    }
+
+    /// Adds a keyframe, replacing any already at `time`
+    pub fn add_key(&mut self, time: Float, value: T, easing: Easing) {
+        self.keyframes.retain(|k| k.time != time);
+
+        let index = self
+            .keyframes
+            .iter()
+            .position(|k| k.time > time)
+            .unwrap_or(self.keyframes.len());
+
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+    }
+
+    /// Keyframes in time order
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Value at `time`, or `None` if the track has no keyframes
+    pub fn value_at(&self, time: Float) -> Option<T> {
+        let next = self.keyframes.iter().position(|k| k.time > time);
+
+        match next {
+            None => self.keyframes.last().map(|k| k.value),
+            Some(0) => Some(self.keyframes[0].value),
+            Some(i) => {
+                let (from, to) = (&self.keyframes[i - 1], &self.keyframes[i]);
+                let t = from
+                    .easing
+                    .apply((time - from.time) / (to.time - from.time));
+
+                Some(from.value.interpolate(&to.value, t))
+            }
+        }
+    }
+}
+
+impl<T: Interpolate> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Animated scale, rotation and translation, applied in that order. Rotation is in radians about
+/// the x, then y, then z axis. Parts without keyframes stay at no change.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformTrack {
+    pub scale: Track<Vector>,
+    pub rotation: Track<Vector>,
+    pub translation: Track<Vector>,
+}
+
+impl TransformTrack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scale.is_empty() && self.rotation.is_empty() && self.translation.is_empty()
+    }
+
+    pub fn transform_at(&self, time: Float) -> TransformationMatrix {
+        let scale = self
+            .scale
+            .value_at(time)
+            .unwrap_or_else(|| Vector::new(1.0, 1.0, 1.0));
+        let rotation = self.rotation.value_at(time).unwrap_or(STATIONARY);
+        let offset = self.translation.value_at(time).unwrap_or(STATIONARY);
+
+        translation(offset.x(), offset.y(), offset.z())
+            * rotation_z(rotation.z())
+            * rotation_y(rotation.y())
+            * rotation_x(rotation.x())
+            * scaling(scale.x(), scale.y(), scale.z())
+    }
+}
+
+/// Animated material parameters. Parameters without keyframes keep the material's own value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaterialTrack {
+    pub color: Track<Color>,
+    pub ambient: Track<Float>,
+    pub diffuse: Track<Float>,
+    pub specular: Track<Float>,
+    pub shininess: Track<Float>,
+}
+
+impl MaterialTrack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&self, material: &mut Material, time: Float) {
+        if let Some(color) = self.color.value_at(time) {
+            material.color = color;
+        }
+
+        let parameters = [
+            (&self.ambient, &mut material.ambient),
+            (&self.diffuse, &mut material.diffuse),
+            (&self.specular, &mut material.specular),
+            (&self.shininess, &mut material.shininess),
+        ];
+
+        for (track, parameter) in parameters {
+            if let Some(value) = track.value_at(time) {
+                *parameter = value;
+            }
+        }
+    }
+}
+
+/// Animation of one of the world's objects, by its index in `RaytracerWorld::objects`. The
+/// animated transform is applied after the object's own, so it moves the object as placed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectTrack {
+    pub object: usize,
+    pub transform: TransformTrack,
+    pub material: MaterialTrack,
+}
+
+impl ObjectTrack {
+    pub fn new(object: usize) -> Self {
+        Self {
+            object,
+            ..Self::default()
+        }
+    }
+}
+
+/// Camera movement. With keyframes for `from` the camera looks from there toward `to` (the
+/// origin unless animated) with `up` (+y unless animated); otherwise it keeps the scene camera's
+/// view. `turn` spins the world about its y axis before it's viewed, for turntables.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CameraTrack {
+    pub from: Track<Point>,
+    pub to: Track<Point>,
+    pub up: Track<Vector>,
+    pub turn: Track<Float>,
+}
+
+impl CameraTrack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Which frames a call to `Animation::render_frames` drew and which it found already on disk
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FramesRendered {
+    pub rendered: Vec<u32>,
+    pub skipped: Vec<u32>,
+}
+
+/// Why `Animation::scene_at` or `Animation::render_frames` stopped
+#[derive(Debug)]
+pub enum AnimationError {
+    Io(io::Error),
+    /// A track made the camera or an object's transform impossible to invert `time` seconds in,
+    /// for example by scaling through zero
+    SingularTransform {
+        time: Float,
+    },
+    /// An `ObjectTrack` names an object past the end of the world's `objects`
+    NoSuchObject {
+        object: usize,
+        objects: usize,
+    },
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnimationError::Io(e) => write!(f, "could not write frame: {}", e),
+            AnimationError::SingularTransform { time } => {
+                write!(f, "{}s in: {}", time, SingularTransform)
+            }
+            AnimationError::NoSuchObject { object, objects } => write!(
+                f,
+                "a track animates object {}, but the world has only {}",
+                object, objects
+            ),
+        }
+    }
+}
+
+impl Error for AnimationError {}
+
+impl From<io::Error> for AnimationError {
+    fn from(e: io::Error) -> Self {
+        AnimationError::Io(e)
+    }
+}
+
+/// A scene with keyframed camera, transforms and materials
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub scene: Scene,
+    pub frames_per_second: Float,
+    pub camera: CameraTrack,
+    pub objects: Vec<ObjectTrack>,
+}
+
+impl Animation {
+    pub fn new(scene: Scene) -> Self {
+        Self {
+            scene,
+            frames_per_second: DEFAULT_FRAMES_PER_SECOND,
+            camera: CameraTrack::new(),
+            objects: vec![],
+        }
+    }
+
+    /// Time in seconds at which frame number `frame` is shown. Frame 0 is at the start.
+    pub fn time_of(&self, frame: u32) -> Float {
+        frame as Float / self.frames_per_second
+    }
+
+    /// Checks that every object track animates an object the world has
+    pub fn check_tracks(&self) -> Result<(), AnimationError> {
+        let objects = self.scene.world.objects().len();
+
+        match self.objects.iter().find(|track| track.object >= objects) {
+            Some(track) => Err(AnimationError::NoSuchObject {
+                object: track.object,
+                objects,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The scene as it is at `time`, or an error if a track animates an object the world doesn't
+    /// have or leaves the camera or an object with a transform that can't be inverted, like a
+    /// scale passing through zero
+    pub fn scene_at(&self, time: Float) -> Result<Scene, AnimationError> {
+        self.check_tracks()?;
+
+        let singular = |_| AnimationError::SingularTransform { time };
+        let mut scene = self.scene.clone();
+
+        if !self.camera.from.is_empty() {
+            let from = self.camera.from.value_at(time).unwrap();
+            let to = self.camera.to.value_at(time).unwrap_or(CENTER_ORIGIN);
+            let up = self
+                .camera
+                .up
+                .value_at(time)
+                .unwrap_or_else(|| Vector::new(0.0, 1.0, 0.0));
+
+            scene
+                .camera
+                .try_set_transform(view_transform(&from, &to, &up))
+                .map_err(singular)?;
+        }
+
+        if let Some(turn) = self.camera.turn.value_at(time) {
+            scene
+                .camera
+                .try_set_transform(scene.camera.transform() * rotation_y(turn))
+                .map_err(singular)?;
+        }
+
+        for track in self.objects.iter() {
+            let object = scene.world.get_object_mut(track.object);
+
+            if !track.transform.is_empty() {
+                object
+                    .try_set_transform(track.transform.transform_at(time) * object.transform())
+                    .map_err(singular)?;
+            }
+
+            track.material.apply(&mut object.material, time);
+        }
+
+        Ok(scene)
+    }
+
+    /// Renders each frame in `frames` to a numbered PPM in `directory`, skipping frames whose
+    /// file already exists so an interrupted run can pick up where it stopped. The tracks are
+    /// checked before anything is rendered.
+    pub fn render_frames<P: AsRef<Path>>(
+        &self,
+        frames: RangeInclusive<u32>,
+        directory: P,
+        renderer: &TileRenderer,
+    ) -> Result<FramesRendered, AnimationError> {
+        self.check_tracks()?;
+
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+
+        let mut result = FramesRendered::default();
+
+        for frame in frames {
+            let path = frame_path(directory, frame);
+
+            if path.exists() {
+                result.skipped.push(frame);
+                continue;
+            }
+
+            let scene = self.scene_at(self.time_of(frame))?;
+            let image = renderer.render(&scene.world, &scene.camera);
+
+            // Written under another name first, so a frame cut short isn't mistaken for done
+            let partial = path.with_extension("ppm.partial");
+            let mut out = BufWriter::new(File::create(&partial)?);
+            image.write_p6(&mut out)?;
+            out.flush()?;
+            drop(out);
+
+            fs::rename(&partial, &path)?;
+            result.rendered.push(frame);
+        }
+
+        Ok(result)
+    }
+}
+
+/// File that frame number `frame` is written to, like `frame_0001.ppm`
+pub fn frame_path<P: AsRef<Path>>(directory: P, frame: u32) -> PathBuf {
+    directory.as_ref().join(format!("frame_{:04}.ppm", frame))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::scenes;
+
+    fn track(keys: &[(Float, Float, Easing)]) -> Track<Float> {
+        let mut track = Track::new();
+
+        for (time, value, easing) in keys.iter() {
+            track.add_key(*time, *value, *easing);
+        }
+
+        track
+    }
+
+    #[test]
+    fn easing_curves_start_at_zero_and_end_at_one() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(0.0, easing.apply(0.0));
+            assert_eq!(1.0, easing.apply(1.0));
+        }
+
+        assert_eq!(0.25, Easing::EaseIn.apply(0.5));
+        assert_eq!(0.75, Easing::EaseOut.apply(0.5));
+        assert_eq!(0.5, Easing::EaseInOut.apply(0.5));
+        assert_eq!(0.0, Easing::Hold.apply(0.99));
+    }
+
+    #[test]
+    fn tracks_interpolate_between_keyframes() {
+        let track = track(&[
+            (2.0, 10.0, Easing::Linear),
+            (0.0, 0.0, Easing::EaseIn),
+            (3.0, 0.0, Easing::Linear),
+        ]);
+
+        assert_eq!(
+            vec![0.0, 2.0, 3.0],
+            track.keyframes().iter().map(|k| k.time).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(0.0), track.value_at(-1.0));
+        assert_eq!(Some(2.5), track.value_at(1.0));
+        assert_eq!(Some(10.0), track.value_at(2.0));
+        assert_eq!(Some(5.0), track.value_at(2.5));
+        assert_eq!(Some(0.0), track.value_at(4.0));
+        assert_eq!(None, Track::<Float>::new().value_at(0.0));
+    }
+
+    #[test]
+    fn adding_a_key_at_the_same_time_replaces_it() {
+        let track = track(&[(1.0, 1.0, Easing::Linear), (1.0, 2.0, Easing::Linear)]);
+
+        assert_eq!(1, track.keyframes().len());
+        assert_eq!(Some(2.0), track.value_at(1.0));
+    }
+
+    #[test]
+    fn rotations_interpolate_the_short_way_round() {
+        let axis = Vector::new(0.0, 1.0, 0.0);
+
+        let mut track = Track::new();
+        track.add_key(
+            0.0,
+            Quaternion::from_axis_angle(&axis, -0.5),
+            Easing::Linear,
+        );
+        track.add_key(
+            1.0,
+            Quaternion::from_axis_angle(&axis, 0.5 - 2.0 * PI),
+            Easing::Linear,
+        );
+
+        let halfway = track.value_at(0.5).unwrap();
+
+        assert!((halfway * Vector::new(0.0, 0.0, 1.0)).equalish_to(&Vector::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn decomposed_transforms_interpolate_part_by_part() {
+        let start = translation(0.0, 0.0, 0.0).decompose().unwrap();
+        let end = TransformationMatrix::identity()
+            .scale(3.0, 3.0, 3.0)
+            .rotate_y(1.0)
+            .translate(4.0, 0.0, 0.0)
+            .decompose()
+            .unwrap();
+
+        let mut track = Track::new();
+        track.add_key(0.0, start, Easing::Linear);
+        track.add_key(1.0, end, Easing::Linear);
+
+        let halfway = track.value_at(0.5).unwrap();
+
+        assert!(halfway.scale.equalish_to(&Vector::new(2.0, 2.0, 2.0)));
+        assert!(halfway.rotation.equalish_to(&Vector::new(0.0, 0.5, 0.0)));
+        assert!(halfway.translation.equalish_to(&Vector::new(2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn object_transforms_and_materials_are_animated() {
+        let mut animation = Animation::new(scenes::default_world(10.0, 10.0));
+
+        let mut inner = ObjectTrack::new(1);
+        inner
+            .transform
+            .translation
+            .add_key(0.0, STATIONARY, Easing::Linear);
+        inner
+            .transform
+            .translation
+            .add_key(1.0, Vector::new(2.0, 0.0, 0.0), Easing::Linear);
+        inner
+            .material
+            .color
+            .add_key(0.0, Color::new(1.0, 0.0, 0.0), Easing::Hold);
+        inner.material.diffuse.add_key(1.0, 0.5, Easing::Linear);
+        animation.objects.push(inner);
+
+        let scene = animation.scene_at(0.5).unwrap();
+        let object = &scene.world.objects()[1];
+
+        assert_eq!(
+            translation(1.0, 0.0, 0.0) * scaling(0.5, 0.5, 0.5),
+            object.transform()
+        );
+        assert_eq!(Color::new(1.0, 0.0, 0.0), object.material.color);
+        assert_eq!(0.5, object.material.diffuse);
+        assert_eq!(Material::new().specular, object.material.specular);
+
+        // The animation's own scene is left alone
+        assert_eq!(
+            scaling(0.5, 0.5, 0.5),
+            animation.scene.world.objects()[1].transform()
+        );
+    }
+
+    #[test]
+    fn camera_turns_and_moves() {
+        let scene = scenes::default_world(10.0, 10.0);
+        let original = scene.camera.transform();
+
+        let mut animation = Animation::new(scene);
+        animation.camera.turn.add_key(0.0, 0.0, Easing::Linear);
+        animation.camera.turn.add_key(1.0, PI, Easing::Linear);
+
+        assert_eq!(
+            original,
+            animation.scene_at(0.0).unwrap().camera.transform()
+        );
+        assert_eq!(
+            original * rotation_y(PI / 2.0),
+            animation.scene_at(0.5).unwrap().camera.transform()
+        );
+
+        animation.camera.turn = Track::new();
+        animation
+            .camera
+            .from
+            .add_key(0.0, Point::new(0.0, 0.0, -10.0), Easing::Linear);
+
+        assert_eq!(
+            view_transform(
+                &Point::new(0.0, 0.0, -10.0),
+                &CENTER_ORIGIN,
+                &Vector::new(0.0, 1.0, 0.0)
+            ),
+            animation.scene_at(3.0).unwrap().camera.transform()
+        );
+    }
+
+    #[test]
+    fn scaling_through_zero_is_an_error() {
+        let mut animation = Animation::new(scenes::default_world(4.0, 4.0));
+        animation.frames_per_second = 2.0;
+
+        // Turns the sphere inside out, passing through nothing at all half a second in
+        let mut flip = ObjectTrack::new(0);
+        flip.transform
+            .scale
+            .add_key(0.0, Vector::new(1.0, 1.0, 1.0), Easing::Linear);
+        flip.transform
+            .scale
+            .add_key(1.0, Vector::new(1.0, 1.0, -1.0), Easing::Linear);
+        animation.objects.push(flip);
+
+        assert!(animation.scene_at(0.25).is_ok());
+        assert!(matches!(
+            animation.scene_at(0.5),
+            Err(AnimationError::SingularTransform { time }) if time == 0.5
+        ));
+
+        let directory = std::env::temp_dir().join(format!("flip_{}", std::process::id()));
+        let error = animation
+            .render_frames(0..=2, &directory, &TileRenderer::new())
+            .unwrap_err();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(matches!(
+            error,
+            AnimationError::SingularTransform { time } if time == 0.5
+        ));
+    }
+
+    #[test]
+    fn tracks_for_missing_objects_are_an_error() {
+        let mut animation = Animation::new(scenes::default_world(4.0, 4.0));
+        animation.objects.push(ObjectTrack::new(1));
+        animation.objects.push(ObjectTrack::new(2));
+
+        assert!(matches!(
+            animation.scene_at(0.0),
+            Err(AnimationError::NoSuchObject {
+                object: 2,
+                objects: 2
+            })
+        ));
+
+        // Found before the first frame, so nothing is written
+        let directory = std::env::temp_dir().join(format!("missing_{}", std::process::id()));
+        let error = animation
+            .render_frames(0..=2, &directory, &TileRenderer::new())
+            .unwrap_err();
+
+        assert!(matches!(error, AnimationError::NoSuchObject { .. }));
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn frames_on_disk_are_skipped() {
+        let directory = std::env::temp_dir().join(format!("frames_{}", std::process::id()));
+
+        let mut animation = Animation::new(scenes::default_world(4.0, 4.0));
+        animation.camera.turn.add_key(0.0, 0.0, Easing::Linear);
+        animation.camera.turn.add_key(1.0, PI, Easing::Linear);
+
+        let renderer = TileRenderer::new();
+
+        let first = animation
+            .render_frames(1..=2, &directory, &renderer)
+            .unwrap();
+        assert_eq!(vec![1, 2], first.rendered);
+        assert!(frame_path(&directory, 1).ends_with("frame_0001.ppm"));
+        assert!(frame_path(&directory, 2).exists());
+
+        let second = animation
+            .render_frames(1..=3, &directory, &renderer)
+            .unwrap();
+        assert_eq!(vec![3], second.rendered);
+        assert_eq!(vec![1, 2], second.skipped);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
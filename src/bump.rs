@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use super::canvas::Canvas;
+use super::math::noise::{perlin, turbulence};
+use super::objects::RaytracerObject;
+use super::{Float, Point, Vector};
+
+// Step used for the central differences that turn a height field into a gradient
+const GRADIENT_DELTA: Float = 0.001;
+const RIPPLE_OCTAVES: u32 = 3;
+
+/// Per-material adjustment of the geometric surface normal, applied after
+/// `RaytracerObject::normal_at` when preparing a hit.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NormalPerturbation {
+    #[default]
+    None,
+    /// Bumps following Perlin noise sampled at `scale` times the object-space point.
+    Noise { scale: Float, amplitude: Float },
+    /// Concentric waves around the object's y axis, roughened by `turbulence`. Good for water.
+    Ripples {
+        frequency: Float,
+        amplitude: Float,
+        turbulence: Float,
+    },
+    /// Tangent-space normal map. Red, green and blue map to the tangent, bitangent and normal.
+    NormalMap(Arc<Canvas>),
+}
+
+impl NormalPerturbation {
+    pub fn perturb(&self, object: &RaytracerObject, world_point: Point, normal: Vector) -> Vector {
+        match self {
+            NormalPerturbation::None => normal,
+            NormalPerturbation::Noise { scale, amplitude } => {
+                let scale = *scale;
+                let height = |p: Point| perlin(p * scale);
+
+                perturb_by_height(object, world_point, normal, *amplitude, height)
+            }
+            NormalPerturbation::Ripples {
+                frequency,
+                amplitude,
+                turbulence: roughness,
+            } => {
+                let (frequency, roughness) = (*frequency, *roughness);
+                let height = |p: Point| {
+                    let distance = (p.x().powi(2) + p.z().powi(2)).sqrt();
+                    (distance * frequency + turbulence(p, RIPPLE_OCTAVES) * roughness).sin()
+                };
+
+                perturb_by_height(object, world_point, normal, *amplitude, height)
+            }
+            NormalPerturbation::NormalMap(map) => {
+                let (u, v) = object.uv_at(world_point);
+                let texel = map.sample_uv(u, v);
+
+                let x = texel.red * 2.0 - 1.0;
+                let y = texel.green * 2.0 - 1.0;
+                let z = texel.blue * 2.0 - 1.0;
+
+                let tangent = object.tangent_at(world_point);
+                let tangent = (tangent - normal * tangent.dot(normal)).norm();
+                let bitangent = normal.cross(tangent);
+
+                (tangent * x + bitangent * y + normal * z).norm()
+            }
+        }
+    }
+}
+
+impl PartialEq for NormalPerturbation {
+    fn eq(&self, other: &Self) -> bool {
+        use NormalPerturbation::*;
+
+        match (self, other) {
+            (None, None) => true,
+            (
+                Noise { scale, amplitude },
+                Noise {
+                    scale: other_scale,
+                    amplitude: other_amplitude,
+                },
+            ) => scale == other_scale && amplitude == other_amplitude,
+            (
+                Ripples {
+                    frequency,
+                    amplitude,
+                    turbulence,
+                },
+                Ripples {
+                    frequency: other_frequency,
+                    amplitude: other_amplitude,
+                    turbulence: other_turbulence,
+                },
+            ) => {
+                frequency == other_frequency
+                    && amplitude == other_amplitude
+                    && turbulence == other_turbulence
+            }
+            (NormalMap(map), NormalMap(other_map)) => Arc::ptr_eq(map, other_map),
+            _ => false,
+        }
+    }
+}
+
+// Tilts the normal against the gradient of an object-space height field
+fn perturb_by_height<F>(
+    object: &RaytracerObject,
+    world_point: Point,
+    normal: Vector,
+    amplitude: Float,
+    height: F,
+) -> Vector
+where
+    F: Fn(Point) -> Float,
+{
+    let inverse = object.inverse_transform();
+    let p = inverse * world_point;
+
+    let dx = Vector::new(GRADIENT_DELTA, 0.0, 0.0);
+    let dy = Vector::new(0.0, GRADIENT_DELTA, 0.0);
+    let dz = Vector::new(0.0, 0.0, GRADIENT_DELTA);
+
+    let gradient = Vector::new(
+        height(p + dx) - height(p - dx),
+        height(p + dy) - height(p - dy),
+        height(p + dz) - height(p - dz),
+    ) / (2.0 * GRADIENT_DELTA);
+
+    let gradient = &inverse.transposed() * gradient;
+    let tangential = gradient - normal * gradient.dot(normal);
+
+    (normal - tangential * amplitude).norm()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::math::transforms::scaling;
+    use crate::{Intersection, Ray, CENTER_ORIGIN};
+
+    #[test]
+    fn no_perturbation_keeps_the_normal() {
+        let sphere = RaytracerObject::new_sphere(0, CENTER_ORIGIN);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+
+        let actual = NormalPerturbation::None.perturb(&sphere, Point::new(0.0, 1.0, 0.0), normal);
+
+        assert_eq!(normal, actual);
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_the_normal() {
+        let map = Canvas::of_color(4, 4, Color::new(0.5, 0.5, 1.0));
+        let perturbation = NormalPerturbation::NormalMap(Arc::new(map));
+
+        let mut sphere = RaytracerObject::new_sphere(0, CENTER_ORIGIN);
+        sphere.set_transform(scaling(2.0, 2.0, 2.0));
+
+        let point = Point::new(0.0, 0.0, -2.0);
+        let normal = sphere.normal_at(point);
+
+        let actual = perturbation.perturb(&sphere, point, normal);
+
+        assert!(
+            normal.equalish_to(&actual),
+            "Expected {:?} but got {:?}",
+            normal,
+            actual
+        );
+    }
+
+    #[test]
+    fn normal_map_tilts_the_normal_toward_the_tangent() {
+        let map = Canvas::of_color(4, 4, Color::new(1.0, 0.5, 0.5));
+        let perturbation = NormalPerturbation::NormalMap(Arc::new(map));
+
+        let plane = RaytracerObject::new_plane(0, CENTER_ORIGIN);
+        let point = Point::new(0.25, 0.0, 0.25);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+
+        let actual = perturbation.perturb(&plane, point, normal);
+
+        let expected = Vector::new(1.0, 0.0, 0.0);
+        assert!(
+            expected.equalish_to(&actual),
+            "Expected {:?} but got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn prepared_hits_see_the_perturbed_normal() {
+        let map = Canvas::of_color(4, 4, Color::new(1.0, 0.5, 0.5));
+
+        let mut plane = RaytracerObject::new_plane(0, CENTER_ORIGIN);
+        plane.material.normal_perturbation = NormalPerturbation::NormalMap(Arc::new(map));
+
+        let ray = Ray::new(Point::new(0.25, 1.0, 0.25), Vector::new(0.0, -1.0, 0.0));
+        let hit = Intersection {
+            time: 1.0,
+            object: Arc::new(plane),
+        };
+
+        let comps = hit.prepare_computations(&ray);
+
+        let expected = Vector::new(1.0, 0.0, 0.0);
+        assert!(
+            comps.normalv.equalish_to(&expected),
+            "Expected {:?} but got {:?}",
+            expected,
+            comps.normalv
+        );
+        assert!(comps.over_point.y() > comps.point.y());
+    }
+
+    #[test]
+    fn ripples_tilt_the_normal_but_keep_it_normalized() {
+        let perturbation = NormalPerturbation::Ripples {
+            frequency: 10.0,
+            amplitude: 0.1,
+            turbulence: 0.0,
+        };
+
+        let plane = RaytracerObject::new_plane(0, CENTER_ORIGIN);
+        let point = Point::new(0.3, 0.0, 0.0);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+
+        let actual = perturbation.perturb(&plane, point, normal);
+
+        assert!((actual.mag() - 1.0).abs() < 1.0e-4);
+        assert!(
+            actual.x().abs() > 0.01,
+            "Expected a tilted normal but got {:?}",
+            actual
+        );
+        assert!(actual.z().abs() < 1.0e-4);
+    }
+}
@@ -1,12 +1,20 @@
 use super::color::{Color, BLACK};
+use super::Float;
+
+pub mod diff;
+pub mod hdr;
+pub mod netpbm;
+pub mod png;
+pub mod tonemap;
 
 const PPM_VERSION: &str = "P3";
 const MAX_PPM_LINE_LENGTH: usize = 70;
-const MAX_COLOR_VALUE: f32 = 1.0;
-const MIN_COLOR_VALUE: f32 = 0.0;
+const MAX_COLOR_VALUE: Float = 1.0;
+const MIN_COLOR_VALUE: Float = 0.0;
 const PPM_MAX_COLOR_VALUE: u32 = 255;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Canvas {
     pub width: u32,
     pub height: u32,
@@ -44,6 +52,18 @@ impl Canvas {
         self.pixels[i as usize]
     }
 
+    /// Nearest-neighbour lookup by texture coordinates, wrapping outside [0, 1). `v` runs bottom
+    /// to top.
+    pub fn sample_uv(&self, u: Float, v: Float) -> Color {
+        let u = u - u.floor();
+        let v = v - v.floor();
+
+        let x = (u * self.width as Float) as u32;
+        let y = ((1.0 - v) * self.height as Float) as u32;
+
+        self.pixel_at(x.min(self.width - 1), y.min(self.height - 1))
+    }
+
     pub fn to_ppm(&self) -> String {
         let mut ppm = String::new();
         let headers = format!(
@@ -100,9 +120,9 @@ impl Canvas {
     }
 }
 
-fn within_bounds(color_value: f32) -> u32 {
+fn within_bounds(color_value: Float) -> u32 {
     let bounded =
-        color_value.max(MIN_COLOR_VALUE).min(MAX_COLOR_VALUE) * (PPM_MAX_COLOR_VALUE as f32);
+        color_value.max(MIN_COLOR_VALUE).min(MAX_COLOR_VALUE) * (PPM_MAX_COLOR_VALUE as Float);
     let bounded = bounded.round();
 
     bounded as u32
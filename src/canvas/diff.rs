@@ -0,0 +1,169 @@
+use super::Canvas;
+use crate::color::{Color, BLACK};
+use crate::Float;
+
+/// Differences between two same-sized canvases
+#[derive(Clone, Debug)]
+pub struct CanvasDiff {
+    /// Absolute per-channel error at every pixel
+    pub difference: Canvas,
+    /// Root mean square error over every channel of every pixel
+    pub rmse: Float,
+    /// Peak signal to noise ratio in decibels, taking 1.0 as the peak. Infinite for identical
+    /// images.
+    pub psnr: Float,
+    /// Largest error in any single channel
+    pub max_error: Float,
+    /// Number of pixels with at least one channel off by more than the threshold
+    pub pixels_over_threshold: usize,
+}
+
+impl CanvasDiff {
+    /// Heat map of the difference: black where the images agree, then blue, green, yellow and
+    /// red as a pixel's largest channel error approaches `max_error`
+    pub fn false_color(&self) -> Canvas {
+        let scale = if self.max_error > 0.0 {
+            1.0 / self.max_error
+        } else {
+            0.0
+        };
+
+        Canvas {
+            width: self.difference.width,
+            height: self.difference.height,
+            pixels: self
+                .difference
+                .pixels
+                .iter()
+                .map(|p| heat(max_channel(p) * scale))
+                .collect(),
+        }
+    }
+}
+
+impl Canvas {
+    /// Compares the canvas against `other`, counting pixels off by more than `threshold` in any
+    /// channel
+    pub fn diff(&self, other: &Canvas, threshold: Float) -> CanvasDiff {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "Can't compare a {}x{} canvas with a {}x{} one.",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+
+        let pixels: Vec<Color> = self
+            .pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .map(|(a, b)| {
+                Color::new(
+                    (a.red - b.red).abs(),
+                    (a.green - b.green).abs(),
+                    (a.blue - b.blue).abs(),
+                )
+            })
+            .collect();
+
+        let squared: Float = pixels
+            .iter()
+            .map(|p| p.red.powi(2) + p.green.powi(2) + p.blue.powi(2))
+            .sum();
+        let samples = (pixels.len() * 3).max(1) as Float;
+        let mse = squared / samples;
+
+        let max_error = pixels.iter().map(max_channel).fold(0.0, Float::max);
+        let pixels_over_threshold = pixels.iter().filter(|p| max_channel(p) > threshold).count();
+
+        CanvasDiff {
+            difference: Canvas {
+                width: self.width,
+                height: self.height,
+                pixels,
+            },
+            rmse: mse.sqrt(),
+            psnr: -10.0 * mse.log10(),
+            max_error,
+            pixels_over_threshold,
+        }
+    }
+}
+
+fn max_channel(color: &Color) -> Float {
+    color.red.max(color.green).max(color.blue)
+}
+
+// Maps [0, 1] onto black, blue, green, yellow, red
+fn heat(value: Float) -> Color {
+    let stops = [
+        BLACK,
+        Color::new(0.0, 0.0, 1.0),
+        Color::new(0.0, 1.0, 0.0),
+        Color::new(1.0, 1.0, 0.0),
+        Color::new(1.0, 0.0, 0.0),
+    ];
+
+    let position = value.clamp(0.0, 1.0) * (stops.len() - 1) as Float;
+    let index = (position.floor() as usize).min(stops.len() - 2);
+    let t = position - index as Float;
+
+    stops[index] * (1.0 - t) + stops[index + 1] * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    #[test]
+    fn identical_canvases_have_no_error() {
+        let canvas = Canvas::of_color(4, 3, Color::new(0.2, 0.4, 0.6));
+
+        let diff = canvas.diff(&canvas.clone(), 0.0);
+
+        assert_eq!(0.0, diff.rmse);
+        assert!(diff.psnr.is_infinite());
+        assert_eq!(0.0, diff.max_error);
+        assert_eq!(0, diff.pixels_over_threshold);
+        assert!(diff.false_color().pixels.iter().all(|p| *p == BLACK));
+    }
+
+    #[test]
+    fn error_statistics() {
+        let a = Canvas::new(2, 2);
+        let mut b = Canvas::new(2, 2);
+        b.write_pixel(0, 0, Color::new(0.5, 0.0, 0.0));
+        b.write_pixel(1, 1, Color::new(0.0, 0.01, 0.0));
+
+        let diff = a.diff(&b, 0.1);
+
+        let expected_rmse = (((0.25 as Float) + 0.0001) / 12.0).sqrt();
+        assert!((diff.rmse - expected_rmse).abs() < 1.0e-6);
+        assert!((diff.psnr - -20.0 * expected_rmse.log10()).abs() < 1.0e-3);
+        assert_eq!(0.5, diff.max_error);
+        assert_eq!(1, diff.pixels_over_threshold);
+        assert_eq!(Color::new(0.5, 0.0, 0.0), diff.difference.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn false_color_runs_from_black_to_red() {
+        let a = Canvas::new(3, 1);
+        let mut b = Canvas::new(3, 1);
+        b.write_pixel(1, 0, WHITE * 0.125);
+        b.write_pixel(2, 0, WHITE * 0.25);
+
+        let heat_map = a.diff(&b, 0.0).false_color();
+
+        assert_eq!(BLACK, heat_map.pixel_at(0, 0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), heat_map.pixel_at(1, 0));
+        assert_eq!(Color::new(1.0, 0.0, 0.0), heat_map.pixel_at(2, 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn canvases_must_be_the_same_size() {
+        Canvas::new(2, 2).diff(&Canvas::new(2, 3), 0.0);
+    }
+}
@@ -0,0 +1,350 @@
+use std::io::{Read, Result, Write};
+
+use super::netpbm::ParseError;
+use super::Canvas;
+use crate::color::{Color, BLACK};
+use crate::Float;
+
+const SIGNATURE: &str = "#?RADIANCE";
+const FORMAT: &str = "FORMAT=32-bit_rle_rgbe";
+
+// Run-length encoded scanlines are only allowed for widths in this range
+const MIN_RLE_WIDTH: u32 = 8;
+const MAX_RLE_WIDTH: u32 = 0x7fff;
+// Outside this range the shared exponent doesn't fit in a byte
+const MIN_RGBE_VALUE: Float = 1.0e-32;
+const MAX_RGBE_VALUE: Float = 1.0e38;
+
+impl Canvas {
+    /// Radiance RGBE image. Unlike the PPM and PNG writers nothing is clamped above 1, so
+    /// highlights survive for later tone mapping. Negative channels are stored as 0.
+    pub fn write_hdr<W: Write>(&self, mut out: W) -> Result<()> {
+        write!(
+            out,
+            "{}\n{}\n\n-Y {} +X {}\n",
+            SIGNATURE, FORMAT, self.height, self.width
+        )?;
+
+        let mut row = Vec::with_capacity(self.width as usize * 4);
+
+        for pixels in self.pixels.chunks(self.width.max(1) as usize) {
+            row.clear();
+
+            for pixel in pixels {
+                row.extend_from_slice(&to_rgbe(pixel));
+            }
+
+            out.write_all(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a Radiance `.hdr` image, flat or run-length encoded, stored top to bottom and left
+    /// to right
+    pub fn from_hdr<B: AsRef<[u8]>>(data: B) -> std::result::Result<Canvas, ParseError> {
+        let data = data.as_ref();
+        let mut lines = HeaderLines { data, position: 0 };
+
+        let signature = lines.next().ok_or(ParseError::UnexpectedEnd)?;
+        if !signature.starts_with("#?") {
+            return Err(ParseError::UnsupportedFormat(signature.to_string()));
+        }
+
+        loop {
+            let line = lines.next().ok_or(ParseError::UnexpectedEnd)?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            if line.starts_with("FORMAT=") && line != FORMAT {
+                return Err(ParseError::UnsupportedFormat(line.to_string()));
+            }
+        }
+
+        let resolution = lines.next().ok_or(ParseError::UnexpectedEnd)?;
+        let (width, height) = parse_resolution(resolution)?;
+
+        let mut scanlines = Scanlines {
+            data,
+            position: lines.position,
+        };
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+        for _ in 0..height {
+            pixels.extend(scanlines.read(width)?.chunks(4).map(from_rgbe));
+        }
+
+        Ok(Canvas {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Reads the whole of `reader` and parses it with `from_hdr`
+    pub fn from_hdr_reader<R: Read>(mut reader: R) -> std::result::Result<Canvas, ParseError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        Canvas::from_hdr(data)
+    }
+}
+
+// Three mantissas sharing one exponent
+fn to_rgbe(color: &Color) -> [u8; 4] {
+    let red = color.red.clamp(0.0, MAX_RGBE_VALUE);
+    let green = color.green.clamp(0.0, MAX_RGBE_VALUE);
+    let blue = color.blue.clamp(0.0, MAX_RGBE_VALUE);
+
+    let brightest = red.max(green).max(blue);
+
+    if brightest < MIN_RGBE_VALUE || brightest.is_nan() {
+        return [0; 4];
+    }
+
+    // brightest = mantissa * 2^exponent with the mantissa in [0.5, 1)
+    let mut exponent = brightest.log2().floor() as i32 + 1;
+    if brightest / (2.0 as Float).powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+
+    let scale = 256.0 / (2.0 as Float).powi(exponent);
+
+    [
+        (red * scale).min(255.0) as u8,
+        (green * scale).min(255.0) as u8,
+        (blue * scale).min(255.0) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+fn from_rgbe(rgbe: &[u8]) -> Color {
+    if rgbe[3] == 0 {
+        return BLACK;
+    }
+
+    // Mantissas are rounded down when written, so read them back from the middle of their step
+    let scale = (2.0 as Float).powi(rgbe[3] as i32 - (128 + 8));
+
+    Color::new(
+        (rgbe[0] as Float + 0.5) * scale,
+        (rgbe[1] as Float + 0.5) * scale,
+        (rgbe[2] as Float + 0.5) * scale,
+    )
+}
+
+fn parse_resolution(line: &str) -> std::result::Result<(u32, u32), ParseError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+
+    match fields.as_slice() {
+        ["-Y", height, "+X", width] => {
+            let invalid = || ParseError::InvalidHeader(format!("bad resolution {:?}", line));
+
+            let width = width.parse().map_err(|_| invalid())?;
+            let height = height.parse().map_err(|_| invalid())?;
+
+            Ok((width, height))
+        }
+        _ => Err(ParseError::UnsupportedFormat(line.to_string())),
+    }
+}
+
+struct HeaderLines<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Iterator for HeaderLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.data.get(self.position..)?;
+        let length = rest.iter().position(|b| *b == b'\n')?;
+
+        self.position += length + 1;
+
+        std::str::from_utf8(&rest[..length]).ok()
+    }
+}
+
+struct Scanlines<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanlines<'a> {
+    fn byte(&mut self) -> std::result::Result<u8, ParseError> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or(ParseError::UnexpectedEnd)?;
+        self.position += 1;
+
+        Ok(byte)
+    }
+
+    // One row of RGBE quadruples
+    fn read(&mut self, width: u32) -> std::result::Result<Vec<u8>, ParseError> {
+        let start = self.position;
+        let marker = self.data.get(start..start + 4);
+
+        let encoded = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width)
+            && marker.is_some_and(|m| m[0] == 2 && m[1] == 2 && m[2] & 0x80 == 0);
+
+        if !encoded {
+            let length = width as usize * 4;
+            let row = self
+                .data
+                .get(start..start + length)
+                .ok_or(ParseError::UnexpectedEnd)?;
+            self.position += length;
+
+            return Ok(row.to_vec());
+        }
+
+        let marker = marker.unwrap();
+        if ((marker[2] as u32) << 8 | marker[3] as u32) != width {
+            return Err(ParseError::InvalidSample { index: start + 2 });
+        }
+        self.position += 4;
+
+        // Each channel is run-length encoded separately, one after the other
+        let width = width as usize;
+        let mut row = vec![0; width * 4];
+
+        for channel in 0..4 {
+            let mut x = 0;
+
+            while x < width {
+                let index = self.position;
+                let count = self.byte()? as usize;
+
+                let (run, length) = if count > 128 {
+                    (true, count - 128)
+                } else {
+                    (false, count)
+                };
+
+                if length == 0 || x + length > width {
+                    return Err(ParseError::InvalidSample { index });
+                }
+
+                let repeated = if run { self.byte()? } else { 0 };
+
+                for pixel in x..x + length {
+                    row[pixel * 4 + channel] = if run { repeated } else { self.byte()? };
+                }
+
+                x += length;
+            }
+        }
+
+        Ok(row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: Color, actual: Color) {
+        let channels = [
+            (expected.red, actual.red),
+            (expected.green, actual.green),
+            (expected.blue, actual.blue),
+        ];
+
+        // RGBE keeps 8 bits of mantissa relative to the brightest channel
+        let tolerance = expected.red.max(expected.green).max(expected.blue) / 128.0;
+
+        for (e, a) in channels.iter() {
+            assert!(
+                (e - a).abs() <= tolerance,
+                "Expected {:?} but got {:?}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn hdr_round_trips_values_above_one() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(12.5, 0.5, 0.001));
+        canvas.write_pixel(1, 0, Color::new(1.0, 1.0, 1.0));
+        canvas.write_pixel(2, 1, Color::new(0.2, 300.0, 4.0));
+
+        let mut bytes = vec![];
+        canvas.write_hdr(&mut bytes).unwrap();
+
+        let parsed = Canvas::from_hdr_reader(&bytes[..]).unwrap();
+
+        assert_eq!((3, 2), (parsed.width, parsed.height));
+
+        for (expected, actual) in canvas.pixels.iter().zip(parsed.pixels.iter()) {
+            assert_close(*expected, *actual);
+        }
+    }
+
+    #[test]
+    fn black_and_negative_values_become_black() {
+        assert_eq!([0, 0, 0, 0], to_rgbe(&BLACK));
+        assert_eq!([0, 0, 0, 0], to_rgbe(&Color::new(-1.0, 0.0, -5.0)));
+        assert_eq!(BLACK, from_rgbe(&[0, 0, 0, 0]));
+
+        let huge = to_rgbe(&Color::new(Float::INFINITY, 0.0, 0.0));
+        assert_eq!(255, huge[3]);
+    }
+
+    #[test]
+    fn exponent_is_shared_by_the_brightest_channel() {
+        assert_eq!([128, 64, 0, 129], to_rgbe(&Color::new(1.0, 0.5, 0.0)));
+        assert_eq!([128, 0, 0, 132], to_rgbe(&Color::new(8.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn run_length_encoded_scanlines() {
+        let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nEXPOSURE=1.0\n\n-Y 1 +X 8\n".to_vec();
+        bytes.extend_from_slice(&[2, 2, 0, 8]);
+        // Red: a run of 8
+        bytes.extend_from_slice(&[128 + 8, 128]);
+        // Green: 2 literals then a run of 6
+        bytes.extend_from_slice(&[2, 64, 0, 128 + 6, 0]);
+        // Blue: a run of 8
+        bytes.extend_from_slice(&[128 + 8, 0]);
+        // Exponent: a run of 8
+        bytes.extend_from_slice(&[128 + 8, 129]);
+
+        let parsed = Canvas::from_hdr(&bytes).unwrap();
+
+        assert_close(Color::new(1.0, 0.5, 0.0), parsed.pixel_at(0, 0));
+        assert_close(Color::new(1.0, 0.0, 0.0), parsed.pixel_at(7, 0));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        assert!(matches!(
+            Canvas::from_hdr("P3\n"),
+            Err(ParseError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr("#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n"),
+            Err(ParseError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr("#?RADIANCE\n\n+Y 1 +X 1\n"),
+            Err(ParseError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr("#?RADIANCE\n\n-Y one +X 1\n"),
+            Err(ParseError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr("#?RADIANCE\n\n-Y 1 +X 2\n\x01\x02"),
+            Err(ParseError::UnexpectedEnd)
+        ));
+    }
+}
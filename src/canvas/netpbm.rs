@@ -0,0 +1,574 @@
+use std::fmt;
+use std::io::{self, Read, Result, Write};
+
+use super::{within_bounds, Canvas};
+use crate::color::Color;
+use crate::Float;
+
+// Rec. 709 luma weights
+const RED_LUMA: Float = 0.2126;
+const GREEN_LUMA: Float = 0.7152;
+const BLUE_LUMA: Float = 0.0722;
+
+// Negative scale marks little-endian samples
+const PFM_SCALE: &str = "-1.0";
+
+const MAX_SAMPLE_VALUE: u32 = 65_535;
+
+/// Why an image couldn't be read
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    /// The magic number or format isn't one this reader understands
+    UnsupportedFormat(String),
+    /// A width, height, max value or scale is missing or malformed
+    InvalidHeader(String),
+    /// Sample number `index` is out of range, or for run-length encoded data the run starting
+    /// at byte `index` is malformed
+    InvalidSample {
+        index: usize,
+    },
+    /// The data ran out before every pixel was read
+    UnexpectedEnd,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "could not read image: {}", e),
+            ParseError::UnsupportedFormat(magic) => write!(f, "unsupported format {:?}", magic),
+            ParseError::InvalidHeader(reason) => write!(f, "invalid header: {}", reason),
+            ParseError::InvalidSample { index } => write!(f, "invalid sample at {}", index),
+            ParseError::UnexpectedEnd => write!(f, "image data ended early"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+impl Canvas {
+    /// Binary 8-bit RGB PPM. Colors are clamped to [0, 1] like `to_ppm`.
+    pub fn write_p6<W: Write>(&self, mut out: W) -> Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+        let mut row = Vec::with_capacity(self.width as usize * 3);
+
+        for pixels in self.rows() {
+            row.clear();
+
+            for pixel in pixels {
+                row.push(within_bounds(pixel.red) as u8);
+                row.push(within_bounds(pixel.green) as u8);
+                row.push(within_bounds(pixel.blue) as u8);
+            }
+
+            out.write_all(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Binary 8-bit grayscale PGM of each pixel's luminance. Passes that store the same value in
+    /// every channel, like depth, come out unchanged.
+    pub fn write_pgm<W: Write>(&self, mut out: W) -> Result<()> {
+        write!(out, "P5\n{} {}\n255\n", self.width, self.height)?;
+
+        let mut row = Vec::with_capacity(self.width as usize);
+
+        for pixels in self.rows() {
+            row.clear();
+            row.extend(pixels.iter().map(|p| within_bounds(luminance(p)) as u8));
+
+            out.write_all(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Portable float map: 32-bit floats per channel, unclamped, so HDR values survive. Rows
+    /// are stored bottom to top as the format requires.
+    // The casts are only needed when Float is f64
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write_pfm<W: Write>(&self, mut out: W) -> Result<()> {
+        write!(out, "PF\n{} {}\n{}\n", self.width, self.height, PFM_SCALE)?;
+
+        let mut row = Vec::with_capacity(self.width as usize * 12);
+
+        for pixels in self.rows().rev() {
+            row.clear();
+
+            for pixel in pixels {
+                row.extend_from_slice(&(pixel.red as f32).to_le_bytes());
+                row.extend_from_slice(&(pixel.green as f32).to_le_bytes());
+                row.extend_from_slice(&(pixel.blue as f32).to_le_bytes());
+            }
+
+            out.write_all(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses an ASCII (P3) or binary (P6) PPM, a grayscale PGM (P2 or P5) or a PFM (PF or Pf).
+    /// Colors are scaled to [0, 1] by the file's max value; PFM samples are kept as they are.
+    pub fn from_ppm<B: AsRef<[u8]>>(data: B) -> std::result::Result<Canvas, ParseError> {
+        let mut header = Header::new(data.as_ref());
+
+        let magic = header.token().ok_or(ParseError::UnexpectedEnd)?;
+
+        match magic {
+            "P2" => header.read_ascii(1),
+            "P3" => header.read_ascii(3),
+            "P5" => header.read_binary(1),
+            "P6" => header.read_binary(3),
+            "Pf" => header.read_pfm(1),
+            "PF" => header.read_pfm(3),
+            _ => Err(ParseError::UnsupportedFormat(magic.to_string())),
+        }
+    }
+
+    /// Reads the whole of `reader` and parses it with `from_ppm`
+    pub fn from_reader<R: Read>(mut reader: R) -> std::result::Result<Canvas, ParseError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        Canvas::from_ppm(data)
+    }
+
+    fn rows(&self) -> std::slice::Chunks<'_, Color> {
+        self.pixels.chunks(self.width.max(1) as usize)
+    }
+}
+
+fn luminance(color: &Color) -> Float {
+    color.red * RED_LUMA + color.green * GREEN_LUMA + color.blue * BLUE_LUMA
+}
+
+// Whitespace separated header fields, skipping `#` comments
+struct Header<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Header<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn token(&mut self) -> Option<&'a str> {
+        loop {
+            match self.data.get(self.position)? {
+                b'#' => {
+                    while self.data.get(self.position).is_some_and(|b| *b != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+
+        let start = self.position;
+
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.data[start..self.position]).ok()
+    }
+
+    fn field<T: std::str::FromStr>(&mut self, name: &str) -> std::result::Result<T, ParseError> {
+        self.token()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| ParseError::InvalidHeader(format!("bad or missing {}", name)))
+    }
+
+    fn dimensions(&mut self) -> std::result::Result<(u32, u32), ParseError> {
+        Ok((self.field("width")?, self.field("height")?))
+    }
+
+    // Number of samples in the image, checked before anything is allocated for them
+    fn sample_count(
+        width: u32,
+        height: u32,
+        channels: usize,
+    ) -> std::result::Result<usize, ParseError> {
+        // Canvases count their pixels in a u32
+        width
+            .checked_mul(height)
+            .and_then(|pixels| (pixels as usize).checked_mul(channels))
+            .ok_or_else(|| ParseError::InvalidHeader(format!("{}x{} is too large", width, height)))
+    }
+
+    // Whether the binary data holds `count` samples of `size` bytes
+    fn body_holds(&self, count: usize, size: usize) -> bool {
+        count
+            .checked_mul(size)
+            .is_some_and(|length| self.body().len() >= length)
+    }
+
+    fn max_value(&mut self) -> std::result::Result<u32, ParseError> {
+        let max: u32 = self.field("max value")?;
+
+        if max == 0 || max > MAX_SAMPLE_VALUE {
+            return Err(ParseError::InvalidHeader(format!(
+                "max value {} out of range",
+                max
+            )));
+        }
+
+        Ok(max)
+    }
+
+    // Binary data starts after the single whitespace byte ending the header
+    fn body(&self) -> &'a [u8] {
+        self.data.get(self.position + 1..).unwrap_or(&[])
+    }
+
+    fn read_ascii(&mut self, channels: usize) -> std::result::Result<Canvas, ParseError> {
+        let (width, height) = self.dimensions()?;
+        let max = self.max_value()?;
+
+        let count = Self::sample_count(width, height, channels)?;
+
+        // Every sample but the last takes at least a digit and a space, so this is as many as
+        // the rest of the file can hold even if the header claims more
+        let remaining = self.data.len().saturating_sub(self.position);
+        let mut samples = Vec::with_capacity(count.min(remaining / 2 + 1));
+
+        for index in 0..count {
+            let token = self.token().ok_or(ParseError::UnexpectedEnd)?;
+
+            let value: u32 = match token.parse() {
+                Ok(v) if v <= max => v,
+                _ => return Err(ParseError::InvalidSample { index }),
+            };
+
+            samples.push(value as Float / max as Float);
+        }
+
+        Ok(canvas_from_samples(width, height, channels, &samples))
+    }
+
+    fn read_binary(&mut self, channels: usize) -> std::result::Result<Canvas, ParseError> {
+        let (width, height) = self.dimensions()?;
+        let max = self.max_value()?;
+
+        let bytes_per_sample = if max < 256 { 1 } else { 2 };
+        let count = Self::sample_count(width, height, channels)?;
+
+        if !self.body_holds(count, bytes_per_sample) {
+            return Err(ParseError::UnexpectedEnd);
+        }
+
+        let body = self.body();
+
+        let mut samples = Vec::with_capacity(count);
+
+        for (index, sample) in body.chunks(bytes_per_sample).take(count).enumerate() {
+            let value = match sample {
+                [high, low] => u16::from_be_bytes([*high, *low]) as u32,
+                [byte] => *byte as u32,
+                _ => unreachable!(),
+            };
+
+            if value > max {
+                return Err(ParseError::InvalidSample { index });
+            }
+
+            samples.push(value as Float / max as Float);
+        }
+
+        Ok(canvas_from_samples(width, height, channels, &samples))
+    }
+
+    fn read_pfm(&mut self, channels: usize) -> std::result::Result<Canvas, ParseError> {
+        let (width, height) = self.dimensions()?;
+        let scale: Float = self.field("scale")?;
+
+        if scale == 0.0 || !scale.is_finite() {
+            return Err(ParseError::InvalidHeader(format!(
+                "scale {} is not usable",
+                scale
+            )));
+        }
+
+        let count = Self::sample_count(width, height, channels)?;
+
+        if !self.body_holds(count, 4) {
+            return Err(ParseError::UnexpectedEnd);
+        }
+
+        let body = self.body();
+
+        let samples: Vec<Float> = body
+            .chunks(4)
+            .take(count)
+            .map(|b| {
+                let bytes = [b[0], b[1], b[2], b[3]];
+
+                if scale < 0.0 {
+                    f32::from_le_bytes(bytes) as Float
+                } else {
+                    f32::from_be_bytes(bytes) as Float
+                }
+            })
+            .collect();
+
+        let mut canvas = canvas_from_samples(width, height, channels, &samples);
+
+        // Stored bottom row first
+        let rows: Vec<&[Color]> = canvas.rows().rev().collect();
+        canvas.pixels = rows.concat();
+
+        Ok(canvas)
+    }
+}
+
+fn canvas_from_samples(width: u32, height: u32, channels: usize, samples: &[Float]) -> Canvas {
+    let pixels = samples
+        .chunks(channels)
+        .map(|s| match s {
+            [red, green, blue] => Color::new(*red, *green, *blue),
+            [gray] => Color::new(*gray, *gray, *gray),
+            _ => unreachable!(),
+        })
+        .collect();
+
+    Canvas {
+        width,
+        height,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_header(bytes: &[u8], lines: usize) -> (String, &[u8]) {
+        let mut end = 0;
+
+        for _ in 0..lines {
+            end += bytes[end..].iter().position(|b| *b == b'\n').unwrap() + 1;
+        }
+
+        (
+            String::from_utf8(bytes[..end].to_vec()).unwrap(),
+            &bytes[end..],
+        )
+    }
+
+    fn assert_same_pixels(expected: &Canvas, actual: &Canvas) {
+        assert_eq!(
+            (expected.width, expected.height),
+            (actual.width, actual.height)
+        );
+
+        for (e, a) in expected.pixels.iter().zip(actual.pixels.iter()) {
+            assert!(e.equalish_to(a), "Expected {:?} but got {:?}", e, a);
+        }
+    }
+
+    fn sample_canvas() -> Canvas {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        canvas.write_pixel(1, 0, Color::new(0.0, 0.2, 0.0));
+        canvas.write_pixel(2, 1, Color::new(0.4, 0.6, 1.0));
+
+        canvas
+    }
+
+    #[test]
+    fn p3_round_trips() {
+        let canvas = sample_canvas();
+
+        let parsed = Canvas::from_ppm(canvas.to_ppm()).unwrap();
+
+        assert_same_pixels(&canvas, &parsed);
+    }
+
+    #[test]
+    fn p3_allows_comments_and_any_wrapping() {
+        let ppm = "P3\n# made by hand\n2 1 # width and height\n100\n100 0\n0 0 50\n\n0\n";
+
+        let parsed = Canvas::from_ppm(ppm).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.0, 0.0), parsed.pixel_at(0, 0));
+        assert_eq!(Color::new(0.0, 0.5, 0.0), parsed.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn p6_round_trips() {
+        let canvas = sample_canvas();
+
+        let mut bytes = vec![];
+        canvas.write_p6(&mut bytes).unwrap();
+
+        let parsed = Canvas::from_reader(&bytes[..]).unwrap();
+
+        assert_same_pixels(&canvas, &parsed);
+    }
+
+    #[test]
+    fn p6_with_sixteen_bit_samples() {
+        let mut bytes = b"P6 1 1 1000\n".to_vec();
+        bytes.extend_from_slice(&[0x03, 0xe8, 0x01, 0xf4, 0x00, 0x00]);
+
+        let parsed = Canvas::from_ppm(&bytes).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.5, 0.0), parsed.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn pgm_reads_back_as_gray() {
+        let canvas = Canvas::of_color(2, 2, Color::new(0.2, 0.2, 0.2));
+
+        let mut bytes = vec![];
+        canvas.write_pgm(&mut bytes).unwrap();
+
+        let parsed = Canvas::from_ppm(&bytes).unwrap();
+
+        assert_same_pixels(&canvas, &parsed);
+    }
+
+    #[test]
+    fn pfm_round_trips_hdr_values() {
+        let mut canvas = Canvas::new(2, 3);
+        canvas.write_pixel(0, 0, Color::new(4.0, -1.0, 0.25));
+        canvas.write_pixel(1, 2, Color::new(100.0, 0.0, 7.5));
+
+        let mut bytes = vec![];
+        canvas.write_pfm(&mut bytes).unwrap();
+
+        let parsed = Canvas::from_ppm(&bytes).unwrap();
+
+        assert_eq!(canvas.pixels, parsed.pixels);
+    }
+
+    #[test]
+    fn big_endian_grayscale_pfm() {
+        let mut bytes = b"Pf\n1 1\n1.0\n".to_vec();
+        bytes.extend_from_slice(&2.5_f32.to_be_bytes());
+
+        let parsed = Canvas::from_ppm(&bytes).unwrap();
+
+        assert_eq!(Color::new(2.5, 2.5, 2.5), parsed.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn huge_headers_are_errors_rather_than_allocations() {
+        assert!(matches!(
+            Canvas::from_ppm("P3 4000000000 4000000000 255\n1 2 3\n"),
+            Err(ParseError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm("P6 4000000000 4000000000 255\n\x01\x02\x03"),
+            Err(ParseError::InvalidHeader(_))
+        ));
+
+        // Small enough to count, but far more than the body holds
+        assert!(matches!(
+            Canvas::from_ppm("P3 60000 60000 255\n1 2 3\n"),
+            Err(ParseError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            Canvas::from_ppm("PF 60000 60000 -1.0\n\0\0\0\0"),
+            Err(ParseError::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn parse_errors_say_what_went_wrong() {
+        assert!(matches!(
+            Canvas::from_ppm("P7\n1 1\n255\n"),
+            Err(ParseError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm("P3\n1\n255\n"),
+            Err(ParseError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm("P3\n1 1\n0\n"),
+            Err(ParseError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm("P3\n1 1\n255\n0 256 0\n"),
+            Err(ParseError::InvalidSample { index: 1 })
+        ));
+        assert!(matches!(
+            Canvas::from_ppm("P3\n1 1\n255\n0 0\n"),
+            Err(ParseError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(b"P6 2 1 255\n\x00\x00\x00"),
+            Err(ParseError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(""),
+            Err(ParseError::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn p6_is_the_clamped_bytes_of_every_pixel() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::new(1.5, 0.0, 0.0));
+        canvas.write_pixel(1, 0, Color::new(0.0, 0.5, 0.0));
+        canvas.write_pixel(1, 1, Color::new(-0.5, 0.0, 1.0));
+
+        let mut bytes = vec![];
+        canvas.write_p6(&mut bytes).unwrap();
+
+        let (header, data) = split_header(&bytes, 3);
+        assert_eq!("P6\n2 2\n255\n", header);
+        assert_eq!(
+            vec![255, 0, 0, 0, 128, 0, 0, 0, 0, 0, 0, 255],
+            data.to_vec()
+        );
+    }
+
+    #[test]
+    fn pgm_stores_luminance() {
+        let mut canvas = Canvas::of_color(3, 1, Color::new(0.5, 0.5, 0.5));
+        canvas.write_pixel(1, 0, Color::new(0.0, 1.0, 0.0));
+        canvas.write_pixel(2, 0, Color::new(2.0, 2.0, 2.0));
+
+        let mut bytes = vec![];
+        canvas.write_pgm(&mut bytes).unwrap();
+
+        let (header, data) = split_header(&bytes, 3);
+        assert_eq!("P5\n3 1\n255\n", header);
+        assert_eq!(vec![128, 182, 255], data.to_vec());
+    }
+
+    #[test]
+    fn pfm_keeps_hdr_values_bottom_row_first() {
+        let mut canvas = Canvas::new(1, 2);
+        canvas.write_pixel(0, 0, Color::new(4.0, -1.0, 0.25));
+
+        let mut bytes = vec![];
+        canvas.write_pfm(&mut bytes).unwrap();
+
+        let (header, data) = split_header(&bytes, 3);
+        assert_eq!("PF\n1 2\n-1.0\n", header);
+        assert_eq!(24, data.len());
+
+        let floats: Vec<Float> = data
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as Float)
+            .collect();
+
+        assert_eq!(vec![0.0, 0.0, 0.0, 4.0, -1.0, 0.25], floats);
+    }
+}
@@ -0,0 +1,287 @@
+use std::io::{Error, ErrorKind, Result, Write};
+
+use super::Canvas;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const COLOR_TYPE_RGB: u8 = 2;
+const FILTER_NONE: u8 = 0;
+
+// Deflate, 32K window, no preset dictionary, fastest compression level
+const ZLIB_HEADER: [u8; 2] = [0x78, 0x01];
+const MAX_STORED_BLOCK: usize = 65_535;
+
+const ADLER_MODULUS: u32 = 65_521;
+const CRC_POLYNOMIAL: u32 = 0xedb8_8320;
+
+impl Canvas {
+    /// 8-bit RGB PNG. Colors are clamped to [0, 1] and written without compression. PNGs can't
+    /// be empty, so a canvas with no pixels is an `InvalidInput` error.
+    pub fn write_png<W: Write>(&self, out: W) -> Result<()> {
+        self.encode_png(out, 8)
+    }
+
+    /// 16-bit RGB PNG, for images that will be graded further and would band at 8 bits
+    pub fn write_png_16<W: Write>(&self, out: W) -> Result<()> {
+        self.encode_png(out, 16)
+    }
+
+    fn encode_png<W: Write>(&self, mut out: W, bit_depth: u8) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "a PNG needs at least one pixel, not {}x{}",
+                    self.width, self.height
+                ),
+            ));
+        }
+
+        out.write_all(&SIGNATURE)?;
+
+        let mut header = vec![];
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // Bit depth, color type, compression, filter and interlace methods
+        header.extend_from_slice(&[bit_depth, COLOR_TYPE_RGB, 0, 0, 0]);
+        write_chunk(&mut out, b"IHDR", &header)?;
+
+        let scanlines = self.scanlines(bit_depth);
+        write_chunk(&mut out, b"IDAT", &zlib_stored(&scanlines))?;
+
+        write_chunk(&mut out, b"IEND", &[])
+    }
+
+    // Unfiltered image data: each row starts with its filter type byte
+    fn scanlines(&self, bit_depth: u8) -> Vec<u8> {
+        let bytes_per_sample = (bit_depth / 8) as usize;
+        let row_length = 1 + self.width as usize * 3 * bytes_per_sample;
+        let mut data = Vec::with_capacity(row_length * self.height as usize);
+
+        for row in self.pixels.chunks(self.width as usize) {
+            data.push(FILTER_NONE);
+
+            for pixel in row {
+                for channel in &[pixel.red, pixel.green, pixel.blue] {
+                    let channel = channel.clamp(0.0, 1.0);
+
+                    if bit_depth == 16 {
+                        let sample = (channel * 65_535.0).round() as u16;
+                        data.extend_from_slice(&sample.to_be_bytes());
+                    } else {
+                        data.push((channel * 255.0).round() as u8);
+                    }
+                }
+            }
+        }
+
+        data
+    }
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+
+    out.write_all(&crc.finish().to_be_bytes())
+}
+
+// A zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = (data.len() / MAX_STORED_BLOCK).max(1) + 1;
+    let mut stream = Vec::with_capacity(data.len() + blocks * 5 + 6);
+
+    stream.extend_from_slice(&ZLIB_HEADER);
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+
+    stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+
+    // 5552 bytes is the most that can be summed before b could overflow
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+
+        a %= ADLER_MODULUS;
+        b %= ADLER_MODULUS;
+    }
+
+    (b << 16) | a
+}
+
+struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        let mut table = [0; 256];
+
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+
+            for _ in 0..8 {
+                c = if c & 1 == 1 {
+                    CRC_POLYNOMIAL ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+
+            *entry = c;
+        }
+
+        Self {
+            table,
+            crc: 0xffff_ffff,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            let index = ((self.crc ^ *byte as u32) & 0xff) as usize;
+            self.crc = self.table[index] ^ (self.crc >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.crc ^ 0xffff_ffff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::Float;
+
+    struct Decoded {
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        samples: Vec<u16>,
+    }
+
+    // Read back with the png crate, so the files are checked against someone else's reading of
+    // the spec. It verifies the chunk CRCs and the Adler-32 checksum as it goes.
+    fn decode(bytes: &[u8]) -> Decoded {
+        let mut reader = png::Decoder::new(bytes).read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+
+        assert_eq!(png::ColorType::Rgb, info.color_type);
+
+        let samples = match info.bit_depth {
+            png::BitDepth::Sixteen => buffer[..info.buffer_size()]
+                .chunks(2)
+                .map(|s| u16::from_be_bytes([s[0], s[1]]))
+                .collect(),
+            _ => buffer[..info.buffer_size()]
+                .iter()
+                .map(|s| *s as u16)
+                .collect(),
+        };
+
+        Decoded {
+            width: info.width,
+            height: info.height,
+            bit_depth: info.bit_depth as u8,
+            samples,
+        }
+    }
+
+    #[test]
+    fn checksums_match_their_reference_values() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(0xcbf4_3926, crc.finish());
+
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn eight_bit_png_round_trips() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.5, 0.0, 0.0));
+        canvas.write_pixel(1, 0, Color::new(0.0, 0.5, 0.0));
+        canvas.write_pixel(2, 1, Color::new(-0.5, 0.0, 1.0));
+
+        let mut bytes = vec![];
+        canvas.write_png(&mut bytes).unwrap();
+
+        let decoded = decode(&bytes);
+
+        assert_eq!(
+            (3, 2, 8),
+            (decoded.width, decoded.height, decoded.bit_depth)
+        );
+        assert_eq!(&[255, 0, 0, 0, 128, 0], &decoded.samples[..6]);
+        assert_eq!(&[0, 0, 255], &decoded.samples[15..]);
+    }
+
+    #[test]
+    fn sixteen_bit_png_keeps_the_extra_precision() {
+        let canvas = Canvas::of_color(2, 2, Color::new(0.5, 0.25, 1.0));
+
+        let mut bytes = vec![];
+        canvas.write_png_16(&mut bytes).unwrap();
+
+        let decoded = decode(&bytes);
+
+        assert_eq!(16, decoded.bit_depth);
+        assert_eq!(&[32_768, 16_384, 65_535], &decoded.samples[..3]);
+    }
+
+    #[test]
+    fn large_images_span_several_stored_blocks() {
+        let mut canvas = Canvas::new(200, 120);
+
+        for (i, pixel) in canvas.pixels.iter_mut().enumerate() {
+            let shade = (i % 256) as Float / 255.0;
+            *pixel = Color::new(shade, 1.0 - shade, 0.5);
+        }
+
+        let mut bytes = vec![];
+        canvas.write_png(&mut bytes).unwrap();
+
+        let decoded = decode(&bytes);
+
+        assert_eq!(200 * 120 * 3, decoded.samples.len());
+        assert_eq!(&[7, 248, 128], &decoded.samples[21..24]);
+    }
+
+    #[test]
+    fn empty_canvases_are_rejected() {
+        for canvas in [Canvas::new(0, 0), Canvas::new(3, 0)].iter() {
+            let mut bytes = vec![];
+            let error = canvas.write_png(&mut bytes).unwrap_err();
+
+            assert_eq!(ErrorKind::InvalidInput, error.kind());
+            assert!(bytes.is_empty());
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use super::Canvas;
+use crate::color::Color;
+use crate::Float;
+
+// Hable's filmic curve from Uncharted 2
+const HABLE_SHOULDER: Float = 0.15;
+const HABLE_LINEAR_STRENGTH: Float = 0.50;
+const HABLE_LINEAR_ANGLE: Float = 0.10;
+const HABLE_TOE_STRENGTH: Float = 0.20;
+const HABLE_TOE_NUMERATOR: Float = 0.02;
+const HABLE_TOE_DENOMINATOR: Float = 0.30;
+const HABLE_WHITE_POINT: Float = 11.2;
+
+/// Curve squeezing unbounded linear light into [0, 1]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    /// Cut off at 1, which is what the exporters do on their own
+    #[default]
+    Clamp,
+    /// `x / (1 + x)`: gentle, never quite reaches white
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces,
+    /// Hable's filmic curve, normalized so 11.2 maps to white
+    Hable,
+}
+
+impl ToneMap {
+    pub fn apply(&self, value: Float) -> Float {
+        let value = value.max(0.0);
+
+        let mapped = match self {
+            ToneMap::Clamp => value,
+            ToneMap::Reinhard => value / (1.0 + value),
+            ToneMap::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+            ToneMap::Hable => hable(value) / hable(HABLE_WHITE_POINT),
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+/// Conversion from the renderer's linear light to display values: exposure, then tone mapping,
+/// then optionally the sRGB transfer curve. Canvases stay linear; apply this when exporting.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OutputTransform {
+    /// In stops: each +1 doubles the brightness before tone mapping
+    pub exposure: Float,
+    pub tone_map: ToneMap,
+    pub srgb: bool,
+}
+
+impl OutputTransform {
+    pub fn new() -> Self {
+        Self {
+            exposure: 0.0,
+            tone_map: ToneMap::Clamp,
+            srgb: true,
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let gain = (2.0 as Float).powf(self.exposure);
+
+        let transform = |channel: Float| {
+            let mapped = self.tone_map.apply(channel * gain);
+
+            if self.srgb {
+                srgb_encode(mapped)
+            } else {
+                mapped
+            }
+        };
+
+        Color::new(
+            transform(color.red),
+            transform(color.green),
+            transform(color.blue),
+        )
+    }
+}
+
+impl Default for OutputTransform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Canvas {
+    /// Display-ready copy of the canvas, for passing to any of the exporters
+    pub fn tone_mapped(&self, output: &OutputTransform) -> Canvas {
+        Canvas {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|p| output.apply(*p)).collect(),
+        }
+    }
+}
+
+/// The sRGB transfer curve, from linear [0, 1] to encoded [0, 1]
+pub fn srgb_encode(linear: Float) -> Float {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `srgb_encode`, for turning loaded 8-bit images back into linear light
+pub fn srgb_decode(encoded: Float) -> Float {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn hable(x: Float) -> Float {
+    let (a, b, c) = (HABLE_SHOULDER, HABLE_LINEAR_STRENGTH, HABLE_LINEAR_ANGLE);
+    let (d, e, f) = (
+        HABLE_TOE_STRENGTH,
+        HABLE_TOE_NUMERATOR,
+        HABLE_TOE_DENOMINATOR,
+    );
+
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLACK, WHITE};
+
+    #[test]
+    fn every_operator_keeps_black_and_stays_in_range() {
+        let operators = [
+            ToneMap::Clamp,
+            ToneMap::Reinhard,
+            ToneMap::Aces,
+            ToneMap::Hable,
+        ];
+
+        for operator in operators.iter() {
+            assert!(operator.apply(0.0).abs() < 0.001, "{:?}", operator);
+            assert!(operator.apply(-3.0).abs() < 0.001, "{:?}", operator);
+
+            let mut previous = 0.0;
+            for i in 1..100 {
+                let mapped = operator.apply(i as Float * 0.25);
+
+                assert!(mapped >= previous && mapped <= 1.0, "{:?}", operator);
+                previous = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn operators_roll_off_highlights() {
+        assert_eq!(0.5, ToneMap::Reinhard.apply(1.0));
+        assert_eq!(1.0, ToneMap::Clamp.apply(4.0));
+        assert!(ToneMap::Aces.apply(4.0) < 1.0);
+        assert!((ToneMap::Hable.apply(HABLE_WHITE_POINT) - 1.0).abs() < 1.0e-5);
+        assert!(ToneMap::Hable.apply(2.0) < ToneMap::Hable.apply(4.0));
+    }
+
+    #[test]
+    fn srgb_curve_round_trips() {
+        assert_eq!(0.0, srgb_encode(0.0));
+        assert!((srgb_encode(1.0) - 1.0).abs() < 1.0e-6);
+        assert!((srgb_encode(0.18) - 0.4614).abs() < 0.001);
+
+        for i in 0..=20 {
+            let linear = i as Float / 20.0;
+            assert!((srgb_decode(srgb_encode(linear)) - linear).abs() < 1.0e-5);
+        }
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let output = OutputTransform {
+            exposure: 1.0,
+            srgb: false,
+            ..OutputTransform::new()
+        };
+
+        let c = output.apply(Color::new(0.25, 0.5, 2.0));
+
+        assert_eq!(Color::new(0.5, 1.0, 1.0), c);
+    }
+
+    #[test]
+    fn tone_mapping_leaves_the_canvas_linear() {
+        let mut canvas = Canvas::of_color(2, 1, Color::new(0.5, 0.5, 0.5));
+        canvas.write_pixel(1, 0, WHITE * 8.0);
+
+        let output = OutputTransform {
+            tone_map: ToneMap::Reinhard,
+            ..OutputTransform::new()
+        };
+
+        let display = canvas.tone_mapped(&output);
+
+        assert_eq!(WHITE * 8.0, canvas.pixel_at(1, 0));
+        assert!(display.pixel_at(1, 0).red < 1.0);
+        let expected = srgb_encode(1.0 / 3.0);
+        assert!((display.pixel_at(0, 0).red - expected).abs() < 1.0e-6);
+        assert_eq!(BLACK, Canvas::new(1, 1).tone_mapped(&output).pixel_at(0, 0));
+    }
+}
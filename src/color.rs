@@ -1,6 +1,6 @@
 use std::ops;
 
-use super::{equalish, round};
+use super::{equalish, round, Float};
 
 pub const BLACK: Color = Color {
     red: 0.0,
@@ -14,14 +14,15 @@ pub const WHITE: Color = Color {
 };
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
-    pub red: f32,
-    pub green: f32,
-    pub blue: f32,
+    pub red: Float,
+    pub green: Float,
+    pub blue: Float,
 }
 
 impl Color {
-    pub fn new(red: f32, green: f32, blue: f32) -> Self {
+    pub fn new(red: Float, green: Float, blue: Float) -> Self {
         Color { red, green, blue }
     }
 
@@ -68,10 +69,10 @@ impl ops::Sub<Color> for Color {
     }
 }
 
-impl ops::Mul<f32> for Color {
+impl ops::Mul<Float> for Color {
     type Output = Self;
 
-    fn mul(self, rhs: f32) -> Self {
+    fn mul(self, rhs: Float) -> Self {
         Color {
             red: self.red * rhs,
             green: self.green * rhs,
@@ -1,24 +1,56 @@
 use std::ops;
-use std::rc::Rc;
-use std::sync::mpsc;
-use std::thread;
+use std::sync::Arc;
 
 use self::canvas::Canvas;
 use self::color::{Color, BLACK, WHITE};
 use self::light::Light;
-use self::math::transforms::{scaling, TransformationMatrix};
+use self::math::simd::{Float4, Lanes};
+use self::math::transforms::{scaling, SingularTransform, Transform, TransformationMatrix};
+use self::media::{Fog, Medium};
 use self::objects::RaytracerObject;
+use self::packet::PacketTracer;
+use self::render::TileRenderer;
 
+pub mod animation;
+pub mod bump;
 pub mod canvas;
 pub mod color;
 pub mod light;
 pub mod material;
 pub mod math;
+pub mod media;
 pub mod objects;
+pub mod packet;
+pub mod passes;
 pub mod physics;
-
-pub const EPSILON: f32 = 0.00001;
+pub mod progressive;
+pub mod render;
+pub mod scenes;
+
+/// Floating point type of every coordinate, color and matrix. Building with the `f64` feature
+/// trades speed and memory for precision in large scenes.
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Constants like `PI` for `Float`
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
+
+/// Distance secondary rays start off a surface, and below which a length counts as zero. Scaled
+/// to the precision of `Float` so f64 builds don't pay for f32's margins.
+#[cfg(not(feature = "f64"))]
+pub const EPSILON: Float = 0.00001;
+#[cfg(feature = "f64")]
+pub const EPSILON: Float = 0.00000001;
+
+/// Decimal places kept by `round`, and the tolerance of `equalish`. These match the precision of
+/// the book's expected values rather than of `Float`.
 pub const EPSILON_DIGITS: i32 = 5;
+const TOLERANCE: Float = 0.00001;
 
 pub const CENTER_ORIGIN: Point = Point {
     x: 0.0,
@@ -31,19 +63,22 @@ pub const STATIONARY: Vector = Vector {
     z: 0.0,
 };
 
-pub type Time = f32;
-pub type Intersections = Vec<Rc<Intersection>>;
+pub type Time = Float;
+pub type Intersections = Vec<Intersection>;
 
-pub fn round(v: f32) -> f32 {
-    let factor = (10.0 as f32).powi(EPSILON_DIGITS);
+pub fn round(v: Float) -> Float {
+    let factor = (10.0 as Float).powi(EPSILON_DIGITS);
     (v * factor).round() / factor
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RaytracerWorld {
     next_id: usize,
     pub light: Option<Light>,
-    objs: Vec<RaytracerObject>,
+    pub fog: Option<Fog>,
+    pub media: Vec<Medium>,
+    objs: Vec<Arc<RaytracerObject>>,
 }
 
 impl RaytracerWorld {
@@ -51,6 +86,8 @@ impl RaytracerWorld {
         RaytracerWorld {
             next_id: 0,
             light: None,
+            fog: None,
+            media: vec![],
             objs: vec![],
         }
     }
@@ -81,27 +118,31 @@ impl RaytracerWorld {
         RaytracerObject::new_test_shape(id, origin)
     }
 
-    pub fn objects(&self) -> Vec<Rc<RaytracerObject>> {
-        // TODO: Return an iterator of objects, instead. No Vec/Rc necessary
-        self.objs.iter().map({ |o| Rc::new(o.clone()) }).collect()
+    pub fn objects(&self) -> &[Arc<RaytracerObject>] {
+        &self.objs
     }
 
     pub fn add_object(&mut self, obj: RaytracerObject) {
-        self.objs.push(obj);
+        self.objs.push(Arc::new(obj));
     }
 
+    /// Objects are shared with any outstanding intersections, so this copies the object first if
+    /// one is still holding on to it
     pub fn get_object_mut(&mut self, index: usize) -> &mut RaytracerObject {
-        &mut self.objs[index]
+        Arc::make_mut(&mut self.objs[index])
     }
 
     pub fn intersect(&self, ray: &Ray) -> Intersections {
-        // TODO: Eliminate this cloning. More references
-        let mut intersections = self
-            .objs
-            .iter()
-            .map(|o| intersect(Rc::new(o.clone()), &ray))
-            .flatten()
-            .collect::<Intersections>();
+        let mut intersections = Vec::with_capacity(self.objs.len() * 2);
+
+        for object in self.objs.iter() {
+            for time in object.intersect(ray) {
+                intersections.push(Intersection {
+                    time,
+                    object: Arc::clone(object),
+                });
+            }
+        }
 
         intersections.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
 
@@ -109,29 +150,35 @@ impl RaytracerWorld {
     }
 
     pub fn shade_hit(&self, comp: &PrecomputedHit) -> Color {
-        let in_shadow = self.is_shadowed(comp.over_point);
+        let visibility = self.light_transmittance(comp.over_point);
 
-        comp.object.material.lighting(
+        comp.object.material.lighting_with_visibility(
             self.light.unwrap(),
             comp.over_point,
             comp.eyev,
             comp.normalv,
-            in_shadow,
+            visibility,
         )
     }
 
     pub fn color_at(&self, ray: &Ray) -> Color {
         let intersections = self.intersect(ray);
 
-        let hit = intersections.hit();
+        self.color_of_hit(ray, intersections.hit())
+    }
 
-        if hit.is_none() {
-            return BLACK;
-        }
+    /// Color seen along `ray` given its nearest hit, for when that's already been found
+    pub fn color_of_hit(&self, ray: &Ray, hit: Option<&Intersection>) -> Color {
+        let (color, time) = match hit {
+            Some(hit) => {
+                let comps = hit.prepare_computations(ray);
 
-        let comps = hit.unwrap().prepare_computations(ray);
+                (self.shade_hit(&comps), hit.time)
+            }
+            None => (BLACK, Float::INFINITY),
+        };
 
-        self.shade_hit(&comps)
+        self.attenuate(ray, time, color)
     }
 
     pub fn is_shadowed(&self, point: Point) -> bool {
@@ -167,36 +214,37 @@ impl Default for RaytracerWorld {
         s1.material.specular = 0.2;
 
         let mut s2 = world.new_sphere(CENTER_ORIGIN);
-        s2.transform = scaling(0.5, 0.5, 0.5);
+        s2.set_transform(scaling(0.5, 0.5, 0.5));
 
         world.light = Some(Light::new(Point::new(-10.0, 10.0, -10.0), WHITE));
-        world.objs = vec![s1, s2];
+        world.objs = vec![Arc::new(s1), Arc::new(s2)];
 
         world
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
-    x: f32,
-    y: f32,
-    z: f32,
+    x: Float,
+    y: Float,
+    z: Float,
 }
 
 impl Point {
-    pub fn new(x: f32, y: f32, z: f32) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Point { x, y, z }
     }
 
-    pub fn x(&self) -> f32 {
+    pub fn x(&self) -> Float {
         self.x
     }
 
-    pub fn y(&self) -> f32 {
+    pub fn y(&self) -> Float {
         self.y
     }
 
-    pub fn z(&self) -> f32 {
+    pub fn z(&self) -> Float {
         self.z
     }
 
@@ -208,8 +256,13 @@ impl Point {
         Self::new(round(self.x), round(self.y), round(self.z))
     }
 
-    pub fn dot(self, rhs: Self) -> f32 {
-        (self.x * rhs.x) + (self.y * rhs.y) + (self.z * rhs.z)
+    pub fn dot(self, rhs: Self) -> Float {
+        self.lanes().dot3(rhs.lanes())
+    }
+
+    // x, y and z in the first three lanes
+    fn lanes(self) -> Float4 {
+        Float4::new(self.x, self.y, self.z, 0.0)
     }
 }
 
@@ -250,10 +303,10 @@ impl ops::Sub<Vector> for Point {
 }
 
 // TODO: Template for float type
-impl ops::Mul<f32> for Point {
+impl ops::Mul<Float> for Point {
     type Output = Self;
 
-    fn mul(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Self::Output {
             x: self.x * rhs,
             y: self.y * rhs,
@@ -263,10 +316,11 @@ impl ops::Mul<f32> for Point {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector {
-    x: f32,
-    y: f32,
-    z: f32,
+    x: Float,
+    y: Float,
+    z: Float,
 }
 
 impl ops::Add<Vector> for Vector {
@@ -318,10 +372,10 @@ impl ops::Neg for Vector {
 }
 
 // TODO: Template for float type
-impl ops::Mul<f32> for Vector {
+impl ops::Mul<Float> for Vector {
     type Output = Self;
 
-    fn mul(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Self::Output {
             x: self.x * rhs,
             y: self.y * rhs,
@@ -331,10 +385,10 @@ impl ops::Mul<f32> for Vector {
 }
 
 // TODO: Template for float type
-impl ops::Div<f32> for Vector {
+impl ops::Div<Float> for Vector {
     type Output = Self;
 
-    fn div(self, rhs: f32) -> Self::Output {
+    fn div(self, rhs: Float) -> Self::Output {
         Self::Output {
             x: self.x / rhs,
             y: self.y / rhs,
@@ -344,19 +398,19 @@ impl ops::Div<f32> for Vector {
 }
 
 impl Vector {
-    pub fn new(x: f32, y: f32, z: f32) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Vector { x, y, z }
     }
 
-    pub fn x(&self) -> f32 {
+    pub fn x(&self) -> Float {
         self.x
     }
 
-    pub fn y(&self) -> f32 {
+    pub fn y(&self) -> Float {
         self.y
     }
 
-    pub fn z(&self) -> f32 {
+    pub fn z(&self) -> Float {
         self.z
     }
 
@@ -364,7 +418,7 @@ impl Vector {
         equalish(self.x, other.x) && equalish(self.y, other.y) && equalish(self.z, other.z)
     }
 
-    pub fn mag(&self) -> f32 {
+    pub fn mag(&self) -> Float {
         (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
     }
 
@@ -378,16 +432,19 @@ impl Vector {
         }
     }
 
-    pub fn dot(self, rhs: Self) -> f32 {
-        (self.x * rhs.x) + (self.y * rhs.y) + (self.z * rhs.z)
+    pub fn dot(self, rhs: Self) -> Float {
+        self.lanes().dot3(rhs.lanes())
     }
 
     pub fn cross(self, rhs: Self) -> Self {
-        Self {
-            x: (self.y * rhs.z) - (self.z * rhs.y),
-            y: (self.z * rhs.x) - (self.x * rhs.z),
-            z: (self.x * rhs.y) - (self.y * rhs.x),
-        }
+        let [x, y, z, _] = self.lanes().cross(rhs.lanes()).to_array();
+
+        Self { x, y, z }
+    }
+
+    // x, y and z in the first three lanes
+    fn lanes(self) -> Float4 {
+        Float4::new(self.x, self.y, self.z, 0.0)
     }
 
     pub fn rounded(&self) -> Self {
@@ -398,7 +455,7 @@ impl Vector {
     pub fn reflect(&self, normal: &Vector) -> Self {
         let incoming: Vector = *self;
 
-        incoming - *normal * 2.0_f32 * incoming.dot(*normal)
+        incoming - *normal * 2.0 * incoming.dot(*normal)
     }
 }
 
@@ -426,24 +483,34 @@ impl Ray {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Intersection {
     pub time: Time,
-    pub object: Rc<RaytracerObject>,
+    pub object: Arc<RaytracerObject>,
 }
 
 impl Intersection {
     pub fn prepare_computations(&self, ray: &Ray) -> PrecomputedHit {
         let time = self.time;
         let point = ray.position(self.time);
-        let object = Rc::clone(&self.object);
+        let object = Arc::clone(&self.object);
         let eyev = -(ray.direction);
-        let normalv = self.object.normal_at(point);
-        let inside = normalv.dot(eyev) < 0.0;
+        let geometric_normalv = self.object.normal_at(point);
+        let normalv = self.object.material.normal_perturbation.perturb(
+            &self.object,
+            point,
+            geometric_normalv,
+        );
+        let inside = geometric_normalv.dot(eyev) < 0.0;
 
-        let normalv = if inside { -normalv } else { normalv };
+        let (geometric_normalv, normalv) = if inside {
+            (-geometric_normalv, -normalv)
+        } else {
+            (geometric_normalv, normalv)
+        };
 
-        let over_point = point + normalv * EPSILON;
+        // Offset along the unperturbed normal so bumps can't push the point below the surface
+        let over_point = point + geometric_normalv * EPSILON;
 
         PrecomputedHit {
             time,
@@ -457,45 +524,37 @@ impl Intersection {
     }
 }
 
-pub fn intersect(object: Rc<RaytracerObject>, ray: &Ray) -> Intersections {
+pub fn intersect(object: Arc<RaytracerObject>, ray: &Ray) -> Intersections {
     let times = object.intersect(ray);
 
     times
-        .iter()
-        .map({
-            |t| {
-                Rc::new(Intersection {
-                    time: *t,
-                    object: Rc::clone(&object),
-                })
-            }
+        .into_iter()
+        .map(|time| Intersection {
+            time,
+            object: Arc::clone(&object),
         })
         .collect()
 }
 
-pub fn local_intersect(object: Rc<RaytracerObject>, ray: &Ray) -> Intersections {
+pub fn local_intersect(object: Arc<RaytracerObject>, ray: &Ray) -> Intersections {
     let times = object.local_intersect(ray);
 
     times
-        .iter()
-        .map({
-            |t| {
-                Rc::new(Intersection {
-                    time: *t,
-                    object: Rc::clone(&object),
-                })
-            }
+        .into_iter()
+        .map(|time| Intersection {
+            time,
+            object: Arc::clone(&object),
         })
         .collect()
 }
 
 pub trait Hittable {
-    fn hit(&self) -> Option<Rc<Intersection>>;
+    fn hit(&self) -> Option<&Intersection>;
 }
 
 impl Hittable for Intersections {
-    fn hit(&self) -> Option<Rc<Intersection>> {
-        let mut h: Option<Rc<Intersection>> = None;
+    fn hit(&self) -> Option<&Intersection> {
+        let mut h: Option<&Intersection> = None;
         let mut min_t: Time = 0.0;
 
         for i in self.iter() {
@@ -505,12 +564,12 @@ impl Hittable for Intersections {
 
             if h.is_none() {
                 min_t = i.time;
-                h = Some(i.clone());
+                h = Some(i);
             }
 
             if min_t > i.time {
                 min_t = i.time;
-                h = Some(i.clone());
+                h = Some(i);
             }
         }
 
@@ -521,7 +580,7 @@ impl Hittable for Intersections {
 #[derive(Debug)]
 pub struct PrecomputedHit {
     pub time: Time,
-    pub object: Rc<RaytracerObject>,
+    pub object: Arc<RaytracerObject>,
     pub point: Point,
     pub over_point: Point,
     pub eyev: Vector,
@@ -529,24 +588,102 @@ pub struct PrecomputedHit {
     pub inside: bool,
 }
 
-pub fn equalish(a: f32, b: f32) -> bool {
-    (a - b).abs() < EPSILON
+pub fn equalish(a: Float, b: Float) -> bool {
+    (a - b).abs() < TOLERANCE
 }
 
+/// Saved as its size, field of view and transform only. The rest is worked out again on loading,
+/// so a hand-written camera can't disagree with itself.
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "CameraSettings", into = "CameraSettings")
+)]
 pub struct Camera {
-    pub hsize: f32,
-    pub vsize: f32,
-    pub field_of_view: f32,
-    pub transform: TransformationMatrix,
-    pub pixel_size: f32, // Pixel Size
-    pub half_width: f32,
-    pub half_height: f32,
+    pub hsize: Float,
+    pub vsize: Float,
+    pub field_of_view: Float,
+    transform: Transform,
+    pub pixel_size: Float, // Pixel Size
+    pub half_width: Float,
+    pub half_height: Float,
+}
+
+// What's saved of a camera
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CameraSettings {
+    hsize: Float,
+    vsize: Float,
+    field_of_view: Float,
+    transform: Transform,
+}
+
+/// Camera settings that can't produce an image, such as a zero width
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidCamera(String);
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for InvalidCamera {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid camera: {}", self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for InvalidCamera {}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<CameraSettings> for Camera {
+    type Error = InvalidCamera;
+
+    fn try_from(settings: CameraSettings) -> Result<Self, Self::Error> {
+        let CameraSettings {
+            hsize,
+            vsize,
+            field_of_view,
+            transform,
+        } = settings;
+
+        // Written so NaN fails too
+        if !(hsize >= 1.0 && vsize >= 1.0 && hsize.is_finite() && vsize.is_finite()) {
+            return Err(InvalidCamera(format!(
+                "hsize and vsize must be finite and at least 1, not {} and {}",
+                hsize, vsize
+            )));
+        }
+
+        if !(field_of_view > 0.0 && field_of_view < consts::PI) {
+            return Err(InvalidCamera(format!(
+                "field_of_view must be more than 0 and less than π, not {}",
+                field_of_view
+            )));
+        }
+
+        let mut camera = Camera::new(hsize, vsize, field_of_view);
+        camera.transform = transform;
+
+        Ok(camera)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Camera> for CameraSettings {
+    fn from(camera: Camera) -> Self {
+        Self {
+            hsize: camera.hsize,
+            vsize: camera.vsize,
+            field_of_view: camera.field_of_view,
+            transform: camera.transform,
+        }
+    }
 }
 
 impl Camera {
-    pub fn new(hsize: f32, vsize: f32, field_of_view: f32) -> Self {
-        let transform = TransformationMatrix::identity();
+    pub fn new(hsize: Float, vsize: Float, field_of_view: Float) -> Self {
+        let transform = Transform::identity();
 
         let half_view = (field_of_view / 2.0).tan();
         let aspect = hsize / vsize;
@@ -570,9 +707,41 @@ impl Camera {
         }
     }
 
+    pub fn transform(&self) -> TransformationMatrix {
+        self.transform.matrix()
+    }
+
+    /// Replaces the view transform, along with the inverse kept for it. Panics if the
+    /// transform can't be inverted; see `try_set_transform`.
+    pub fn set_transform(&mut self, transform: TransformationMatrix) {
+        if let Err(e) = self.try_set_transform(transform) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Replaces the view transform, or leaves it alone if the new one can't be inverted
+    pub fn try_set_transform(
+        &mut self,
+        transform: TransformationMatrix,
+    ) -> Result<(), SingularTransform> {
+        self.transform = Transform::new(transform)?;
+
+        Ok(())
+    }
+
+    pub fn inverse_transform(&self) -> TransformationMatrix {
+        self.transform.inverse()
+    }
+
     pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let xoffset = (px as f32 + 0.5) * self.pixel_size;
-        let yoffset = (py as f32 + 0.5) * self.pixel_size;
+        self.ray_for_subpixel(px, py, 0.5, 0.5)
+    }
+
+    /// Ray through a point inside the pixel. Offsets run from 0 to 1 across the pixel, so 0.5 is
+    /// its center.
+    pub fn ray_for_subpixel(&self, px: usize, py: usize, dx: Float, dy: Float) -> Ray {
+        let xoffset = (px as Float + dx) * self.pixel_size;
+        let yoffset = (py as Float + dy) * self.pixel_size;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
@@ -592,99 +761,83 @@ impl Camera {
 
     pub fn render(&self, world: &RaytracerWorld) -> Canvas {
         let mut image = Canvas::new(self.hsize as u32, self.vsize as u32);
+        let tracer = PacketTracer::new(world);
 
         for y in 0..(self.vsize as usize) {
-            for x in 0..(self.hsize as usize) {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(&ray);
-                image.write_pixel(x as u32, y as u32, color);
-            }
+            self.trace_row(&tracer, y, &mut image);
         }
 
         image
     }
 
     // TODO: Rename to render_row_to since that's what's actually being rendered
+    /// Renders a single row. Each call works out every object's bounds again, so this is for
+    /// one-off rows; `render` and `render_with` do that once for the whole image.
     pub fn render_column_to(&self, world: &RaytracerWorld, y: usize, image: &mut Canvas) {
-        for x in 0..(self.hsize as usize) {
-            let ray = self.ray_for_pixel(x, y);
-            let color = world.color_at(&ray);
+        self.trace_row(&PacketTracer::new(world), y, image);
+    }
+
+    fn trace_row(&self, tracer: &PacketTracer, y: usize, image: &mut Canvas) {
+        let rays: Vec<Ray> = (0..(self.hsize as usize))
+            .map(|x| self.ray_for_pixel(x, y))
+            .collect();
 
+        for (x, color) in tracer.colors_at(&rays).into_iter().enumerate() {
             image.write_pixel(x as u32, y as u32, color);
         }
     }
 }
 
+/// Renders across every available core. See `render::TileRenderer` for control over the worker
+/// count and tile size.
 pub fn render_threaded(world: RaytracerWorld, camera: Camera) -> Canvas {
-    let mut image = Canvas::new(camera.hsize as u32, camera.vsize as u32);
-
-    let mut handles = vec![];
-
-    let hsize = camera.hsize.round() as usize;
-    let vsize = camera.vsize.round() as usize;
-
-    let mut start_y = 0;
-    let mut end_y = 0;
-
-    let (tx, rx) = mpsc::channel();
-
-    while start_y < vsize && end_y < vsize {
-        let world = world.clone();
-        let camera = camera.clone();
-
-        end_y = start_y + (vsize / 10);
-        if end_y > vsize {
-            end_y = vsize;
-        }
+    TileRenderer::default().render(&world, &camera)
+}
 
-        let mut canvas = Canvas::new(hsize as u32, vsize as u32);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let thread_tx = mpsc::Sender::clone(&tx);
+    fn assert_send_and_sync<T: Send + Sync>() {}
 
-        let handle = thread::spawn(move || {
-            for y in start_y..end_y {
-                camera.render_column_to(&world, y, &mut canvas);
-            }
+    #[test]
+    fn scene_types_can_be_shared_across_threads() {
+        assert_send_and_sync::<RaytracerWorld>();
+        assert_send_and_sync::<Camera>();
+        assert_send_and_sync::<Intersection>();
+        assert_send_and_sync::<PrecomputedHit>();
+    }
 
-            thread_tx.send((start_y, end_y, canvas)).unwrap();
-        });
+    #[test]
+    fn intersections_share_the_world_objects() {
+        let world = RaytracerWorld::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
 
-        handles.push(handle);
+        let xs = world.intersect(&ray);
 
-        start_y = end_y;
+        assert!(Arc::ptr_eq(&xs[0].object, &world.objects()[0]));
     }
 
-    let mut finished_handles = 0;
+    #[test]
+    fn setting_a_transform_updates_its_inverses() {
+        let transform = math::transforms::translation(1.0, 2.0, 3.0) * scaling(2.0, 4.0, 8.0);
 
-    for received in rx {
-        let (start_y, end_y, canvas) = received;
+        let mut sphere = RaytracerObject::new_sphere(1, CENTER_ORIGIN);
+        sphere.set_transform(transform);
 
-        for x in 0..hsize {
-            for y in start_y..end_y {
-                let x = x as u32;
-                let y = y as u32;
-                image.write_pixel(x, y, canvas.pixel_at(x, y));
-            }
-        }
-
-        finished_handles += 1;
+        assert_eq!(transform, sphere.transform());
+        assert_eq!(transform.inverse().unwrap(), sphere.inverse_transform());
+        assert_eq!(
+            transform.inverse().unwrap().transposed(),
+            sphere.inverse_transpose()
+        );
 
-        if finished_handles == handles.len() {
-            break;
-        }
-    }
+        let mut camera = Camera::new(10.0, 10.0, 1.0);
+        camera.set_transform(transform);
 
-    for handle in handles {
-        handle.join().unwrap();
+        assert_eq!(transform.inverse().unwrap(), camera.inverse_transform());
     }
 
-    image.clone()
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
     #[test]
     fn equalish_is_true_for_diff_below_epsilon() {
         assert!(equalish(1.0, 1.000001));
@@ -1008,7 +1161,7 @@ mod tests {
                 y: 2.0,
                 z: 3.0,
             };
-            let expected = (14.0 as f32).sqrt();
+            let expected = (14.0 as Float).sqrt();
             let result = v.mag();
             assert!(
                 equalish(expected, result),
@@ -1022,7 +1175,7 @@ mod tests {
                 y: -2.0,
                 z: -3.0,
             };
-            let expected = (14.0 as f32).sqrt();
+            let expected = (14.0 as Float).sqrt();
             let result = v.mag();
             assert!(
                 equalish(expected, result),
@@ -1057,9 +1210,9 @@ mod tests {
                 y: 2.0,
                 z: 3.0,
             };
-            let x = 1.0 / (14.0 as f32).sqrt();
-            let y = 2.0 / (14.0 as f32).sqrt();
-            let z = 3.0 / (14.0 as f32).sqrt();
+            let x = 1.0 / (14.0 as Float).sqrt();
+            let y = 2.0 / (14.0 as Float).sqrt();
+            let z = 3.0 / (14.0 as Float).sqrt();
             let expected = Vector { x, y, z };
             let result = v.norm();
             assert!(
@@ -1,6 +1,7 @@
 use super::{Color, Point};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Light {
     pub position: Point,
     pub intensity: Color,
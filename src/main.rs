@@ -1,3 +1,395 @@
+extern crate clap;
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use clap::{App, Arg, ArgMatches};
+
+use ray_tracer_challenge::canvas::tonemap::{OutputTransform, ToneMap};
+use ray_tracer_challenge::canvas::Canvas;
+use ray_tracer_challenge::consts::PI;
+use ray_tracer_challenge::render::{RenderOptions, RenderProgress, TileRenderer};
+#[cfg(feature = "serde")]
+use ray_tracer_challenge::scenes::json;
+use ray_tracer_challenge::scenes::yaml;
+use ray_tracer_challenge::scenes::{self, Scene, SceneError, SCENE_NAMES};
+use ray_tracer_challenge::{Camera, Float};
+
+// Size of built-in scenes unless overridden
+const DEFAULT_WIDTH: Float = 400.0;
+const DEFAULT_HEIGHT: Float = 200.0;
+const DEFAULT_OUTPUT: &str = "output.png";
+
+// Largest image rendered, in pixels. Beyond this the canvas alone takes gigabytes.
+const MAX_PIXELS: Float = 100_000_000.0;
+
+#[cfg(feature = "serde")]
+const SCENE_EXTENSIONS: &str = ".yml, .yaml or .json";
+#[cfg(not(feature = "serde"))]
+const SCENE_EXTENSIONS: &str = ".yml or .yaml";
+
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const FORMATS: [&str; 7] = ["ppm", "ppm-ascii", "pgm", "pfm", "hdr", "png", "png16"];
+const TONE_MAPS: [&str; 4] = ["clamp", "reinhard", "aces", "hable"];
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Scene(String, SceneError),
+    Output(String, io::Error),
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Scene(_, _) | CliError::Output(_, _) => EXIT_FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::Scene(path, e) => write!(f, "{}: {}", path, e),
+            CliError::Output(path, e) => write!(f, "could not write {}: {}", path, e),
+        }
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("ray_tracer_challenge")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Renders a built-in scene or a scene file to an image.")
+        .arg(
+            Arg::with_name("scene")
+                .help("Built-in scene name (see --list-scenes) or path to a scene file")
+                .required_unless("list-scenes")
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("list-scenes")
+                .help("Prints the built-in scene names and exits")
+                .long("list-scenes"),
+        )
+        .arg(
+            Arg::with_name("width")
+                .help("Image width in pixels")
+                .long("width")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("height")
+                .help("Image height in pixels")
+                .long("height")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fov")
+                .help("Horizontal field of view in radians. Defaults to the scene's.")
+                .long("fov")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .help("Number of render threads. Defaults to one per core.")
+                .short("j")
+                .long("threads")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("samples")
+                .help("Rays per pixel, for smoother edges")
+                .short("s")
+                .long("samples")
+                .takes_value(true)
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .help("Image to write")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .default_value(DEFAULT_OUTPUT),
+        )
+        .arg(
+            Arg::with_name("format")
+                .help("Output format. Defaults to the output file's extension.")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&FORMATS),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .help("Exposure adjustment in stops, for 8- and 16-bit formats")
+                .long("exposure")
+                .takes_value(true)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("tone-map")
+                .help("Tone mapping operator, for 8- and 16-bit formats")
+                .long("tone-map")
+                .takes_value(true)
+                .possible_values(&TONE_MAPS)
+                .default_value("clamp"),
+        )
+        .arg(
+            Arg::with_name("linear")
+                .help("Writes 8- and 16-bit formats without sRGB encoding")
+                .long("linear"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .help("Prints nothing but errors")
+                .short("q")
+                .long("quiet"),
+        )
+}
+
+fn parse<T: std::str::FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>, CliError> {
+    match matches.value_of(name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| CliError::Usage(format!("Invalid value for --{}: {}", name, value))),
+        None => Ok(None),
+    }
+}
+
+// Warnings about anything skipped in a YAML file go to stderr unless `quiet`
+fn load_scene(name: &str, quiet: bool) -> Result<Scene, CliError> {
+    if let Some(scene) = scenes::by_name(name, DEFAULT_WIDTH, DEFAULT_HEIGHT) {
+        return Ok(scene);
+    }
+
+    let path = Path::new(name);
+
+    if !path.exists() {
+        return Err(CliError::Usage(format!(
+            "{} is neither a built-in scene ({}) nor a file",
+            name,
+            SCENE_NAMES.join(", ")
+        )));
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "yml" | "yaml" => {
+            let (scene, warnings) =
+                yaml::load_with_warnings(path).map_err(|e| CliError::Scene(name.to_string(), e))?;
+
+            if !quiet {
+                for warning in warnings {
+                    eprintln!("warning: {}: {}", name, warning);
+                }
+            }
+
+            Ok(scene)
+        }
+        #[cfg(feature = "serde")]
+        "json" => json::load(path).map_err(|e| CliError::Scene(name.to_string(), e)),
+        _ => Err(CliError::Usage(format!(
+            "Don't know how to load scene file {}. Scene files end in {}.",
+            name, SCENE_EXTENSIONS
+        ))),
+    }
+}
+
+// Same view with a different image size or field of view
+fn resize_camera(camera: &Camera, width: Float, height: Float, field_of_view: Float) -> Camera {
+    let mut resized = Camera::new(width, height, field_of_view);
+    resized.set_transform(camera.transform());
+
+    resized
+}
+
+fn output_format<'a>(matches: &'a ArgMatches, output: &'a str) -> Result<&'a str, CliError> {
+    if let Some(format) = matches.value_of("format") {
+        return Ok(format);
+    }
+
+    let extension = Path::new(output)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+
+    match extension.to_ascii_lowercase().as_str() {
+        "ppm" => Ok("ppm"),
+        "pgm" => Ok("pgm"),
+        "pfm" => Ok("pfm"),
+        "hdr" => Ok("hdr"),
+        "png" => Ok("png"),
+        _ => Err(CliError::Usage(format!(
+            "Can't tell the format of {} from its extension. Use --format.",
+            output
+        ))),
+    }
+}
+
+fn write_image(
+    canvas: &Canvas,
+    format: &str,
+    file: File,
+    display: &OutputTransform,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(file);
+
+    match format {
+        "pfm" => canvas.write_pfm(&mut out)?,
+        "hdr" => canvas.write_hdr(&mut out)?,
+        _ => {
+            let canvas = canvas.tone_mapped(display);
+
+            match format {
+                "ppm" => canvas.write_p6(&mut out)?,
+                "ppm-ascii" => out.write_all(canvas.to_ppm().as_bytes())?,
+                "pgm" => canvas.write_pgm(&mut out)?,
+                "png16" => canvas.write_png_16(&mut out)?,
+                _ => canvas.write_png(&mut out)?,
+            }
+        }
+    }
+
+    out.flush()
+}
+
+fn report_progress(progress: RenderProgress) {
+    let eta = progress
+        .eta()
+        .map(|eta| format!(", {:.1}s left", eta.as_secs_f32()))
+        .unwrap_or_default();
+
+    eprint!(
+        "\rRendering: {:5.1}% ({}/{} tiles{})   ",
+        progress.fraction() * 100.0,
+        progress.completed,
+        progress.total,
+        eta
+    );
+}
+
+fn run(matches: &ArgMatches) -> Result<(), CliError> {
+    if matches.is_present("list-scenes") {
+        for name in SCENE_NAMES.iter() {
+            println!("{}", name);
+        }
+
+        return Ok(());
+    }
+
+    let quiet = matches.is_present("quiet");
+    let scene_name = matches.value_of("scene").unwrap();
+    let Scene { world, camera } = load_scene(scene_name, quiet)?;
+
+    let width = parse(matches, "width")?.unwrap_or(camera.hsize);
+    let height = parse(matches, "height")?.unwrap_or(camera.vsize);
+    let field_of_view = parse(matches, "fov")?.unwrap_or(camera.field_of_view);
+
+    // Written so NaN fails too
+    if !(width >= 1.0 && height >= 1.0 && width.is_finite() && height.is_finite()) {
+        return Err(CliError::Usage(
+            "Width and height must be finite and at least 1".to_string(),
+        ));
+    }
+
+    if width.floor() * height.floor() > MAX_PIXELS {
+        return Err(CliError::Usage(format!(
+            "Images can be at most {} pixels, not {}x{}",
+            MAX_PIXELS,
+            width.floor(),
+            height.floor()
+        )));
+    }
+
+    // Nothing is visible at 0, and at π or more the view turns inside out
+    if !(field_of_view > 0.0 && field_of_view < PI) {
+        return Err(CliError::Usage(format!(
+            "The field of view must be more than 0 and less than π radians, not {}",
+            field_of_view
+        )));
+    }
+
+    let camera = resize_camera(&camera, width.floor(), height.floor(), field_of_view);
+
+    let mut renderer = TileRenderer::new();
+    if let Some(threads) = parse(matches, "threads")? {
+        renderer.workers = threads;
+    }
+    renderer.samples = parse(matches, "samples")?.unwrap();
+
+    let output = matches.value_of("output").unwrap();
+    let format = output_format(matches, output)?;
+
+    let display = OutputTransform {
+        exposure: parse(matches, "exposure")?.unwrap(),
+        tone_map: match matches.value_of("tone-map").unwrap() {
+            "reinhard" => ToneMap::Reinhard,
+            "aces" => ToneMap::Aces,
+            "hable" => ToneMap::Hable,
+            _ => ToneMap::Clamp,
+        },
+        srgb: !matches.is_present("linear"),
+    };
+
+    if !quiet {
+        eprintln!(
+            "Rendering {} at {}x{} with {} thread(s), {} sample(s) per pixel",
+            scene_name, camera.hsize, camera.vsize, renderer.workers, renderer.samples
+        );
+    }
+
+    // Before rendering, so a bad path doesn't waste a long render
+    let file = File::create(output).map_err(|e| CliError::Output(output.to_string(), e))?;
+
+    let started = Instant::now();
+
+    let options = RenderOptions {
+        progress: if quiet { None } else { Some(&report_progress) },
+        ..RenderOptions::new()
+    };
+    let canvas = renderer.render_with(&world, &camera, &options).canvas();
+
+    if !quiet {
+        eprintln!("\nRendered in {:.2}s", started.elapsed().as_secs_f32());
+    }
+
+    write_image(&canvas, format, file, &display)
+        .map_err(|e| CliError::Output(output.to_string(), e))?;
+
+    if !quiet {
+        eprintln!("Wrote {} ({})", output, format);
+    }
+
+    Ok(())
+}
+
 fn main() {
-    println!("See the examples!");
+    let matches = match app().get_matches_safe() {
+        Ok(matches) => matches,
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            process::exit(EXIT_USAGE);
+        }
+        // Help and version
+        Err(e) => e.exit(),
+    };
+
+    if let Err(e) = run(&matches) {
+        eprintln!("error: {}", e);
+        process::exit(e.exit_code());
+    }
 }
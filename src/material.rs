@@ -1,14 +1,17 @@
+use super::bump::NormalPerturbation;
 use super::color::{Color, BLACK};
 use super::light::Light;
-use super::{Point, Vector};
+use super::{Float, Point, Vector};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub color: Color,
-    pub ambient: f32,
-    pub diffuse: f32,
-    pub specular: f32,
-    pub shininess: f32,
+    pub ambient: Float,
+    pub diffuse: Float,
+    pub specular: Float,
+    pub shininess: Float,
+    pub normal_perturbation: NormalPerturbation,
 }
 
 impl Material {
@@ -19,6 +22,7 @@ impl Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            normal_perturbation: NormalPerturbation::None,
         }
     }
 
@@ -29,6 +33,21 @@ impl Material {
         eyev: Vector,
         normalv: Vector,
         in_shadow: bool,
+    ) -> Color {
+        let visibility = if in_shadow { 0.0 } else { 1.0 };
+
+        self.lighting_with_visibility(light, point, eyev, normalv, visibility)
+    }
+
+    /// Like `lighting`, but with the fraction of the light that reaches the point (1.0 fully lit,
+    /// 0.0 fully shadowed) instead of a yes/no shadow flag
+    pub fn lighting_with_visibility(
+        &self,
+        light: Light,
+        point: Point,
+        eyev: Vector,
+        normalv: Vector,
+        visibility: Float,
     ) -> Color {
         let material = self;
 
@@ -38,7 +57,7 @@ impl Material {
 
         let ambient: Color = effective_color * material.ambient;
 
-        let light_dot_normal: f32 = lightv.dot(normalv);
+        let light_dot_normal: Float = lightv.dot(normalv);
 
         let mut diffuse: Color = BLACK;
         let mut specular: Color = BLACK;
@@ -46,19 +65,15 @@ impl Material {
         if light_dot_normal >= 0.0 {
             diffuse = effective_color * material.diffuse * light_dot_normal;
             let reflectv: Vector = (lightv * -1.0).reflect(&normalv);
-            let reflect_dot_eye: f32 = reflectv.dot(eyev);
+            let reflect_dot_eye: Float = reflectv.dot(eyev);
 
             if reflect_dot_eye > 0.0 {
-                let factor: f32 = reflect_dot_eye.powf(material.shininess);
+                let factor: Float = reflect_dot_eye.powf(material.shininess);
                 specular = light.intensity * material.specular * factor;
             }
         }
 
-        if in_shadow {
-            ambient
-        } else {
-            ambient + diffuse + specular
-        }
+        ambient + (diffuse + specular) * visibility
     }
 }
 
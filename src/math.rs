@@ -1,9 +1,14 @@
 use std::ops;
 
-use super::{round, Point, Vector};
+use super::{round, Float, Point, Vector};
 
+pub mod noise;
+pub mod quaternion;
+pub mod simd;
 pub mod transforms;
 
+use self::simd::Float4;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AnyMatrix {
     M2x2(Matrix2x2),
@@ -14,11 +19,11 @@ pub enum AnyMatrix {
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Matrix2x2 {
-    values: [f32; 4],
+    values: [Float; 4],
 }
 
 impl Matrix2x2 {
-    pub fn new(values: [f32; 4]) -> Self {
+    pub fn new(values: [Float; 4]) -> Self {
         Self { values }
     }
 
@@ -26,7 +31,7 @@ impl Matrix2x2 {
         Self::new([0.0; 4])
     }
 
-    pub fn determinant(&self) -> f32 {
+    pub fn determinant(&self) -> Float {
         let a = self[[0, 0]];
         let b = self[[0, 1]];
         let c = self[[1, 0]];
@@ -35,17 +40,17 @@ impl Matrix2x2 {
         (a * d) - (b * c)
     }
 
-    pub fn minor(&self) -> f32 {
+    pub fn minor(&self) -> Float {
         self.determinant()
     }
 
-    pub fn cofactor(&self) -> f32 {
+    pub fn cofactor(&self) -> Float {
         self.minor()
     }
 }
 
 impl ops::Index<[usize; 2]> for Matrix2x2 {
-    type Output = f32;
+    type Output = Float;
 
     fn index(&self, index: [usize; 2]) -> &Self::Output {
         let row = index[0];
@@ -66,11 +71,11 @@ impl ops::IndexMut<[usize; 2]> for Matrix2x2 {
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Matrix3x3 {
-    values: [f32; 9],
+    values: [Float; 9],
 }
 
 impl Matrix3x3 {
-    pub fn new(values: [f32; 9]) -> Self {
+    pub fn new(values: [Float; 9]) -> Self {
         Self { values }
     }
 
@@ -78,8 +83,8 @@ impl Matrix3x3 {
         Self::new([0.0; 9])
     }
 
-    pub fn determinant(&self) -> f32 {
-        let mut determinant: f32 = 0.0;
+    pub fn determinant(&self) -> Float {
+        let mut determinant: Float = 0.0;
 
         for c in 0..3 {
             let element = self[[0, c]];
@@ -116,9 +121,9 @@ impl Matrix3x3 {
     }
 
     // Credit to https://www.mathsisfun.com/algebra/matrix-determinant.html for this optimization
-    pub fn minor(&self, row: usize, col: usize) -> f32 {
+    pub fn minor(&self, row: usize, col: usize) -> Float {
         let mut i = 0;
-        let mut m: [f32; 4] = [0.0; 4];
+        let mut m: [Float; 4] = [0.0; 4];
 
         for r in 0..=2 {
             if r == row {
@@ -138,7 +143,7 @@ impl Matrix3x3 {
         (m[0] * m[3]) - (m[1] * m[2])
     }
 
-    pub fn cofactor(&self, row: usize, col: usize) -> f32 {
+    pub fn cofactor(&self, row: usize, col: usize) -> Float {
         let minor = self.minor(row, col);
 
         if (row + col) % 2 == 0 {
@@ -151,7 +156,7 @@ impl Matrix3x3 {
 }
 
 impl ops::Index<[usize; 2]> for Matrix3x3 {
-    type Output = f32;
+    type Output = Float;
 
     fn index(&self, index: [usize; 2]) -> &Self::Output {
         let row = index[0];
@@ -172,11 +177,11 @@ impl ops::IndexMut<[usize; 2]> for Matrix3x3 {
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Matrix4x1 {
-    values: [f32; 4],
+    values: [Float; 4],
 }
 
 impl Matrix4x1 {
-    pub fn new(values: [f32; 4]) -> Self {
+    pub fn new(values: [Float; 4]) -> Self {
         Self { values }
     }
 
@@ -188,7 +193,7 @@ impl Matrix4x1 {
 }
 
 impl ops::Index<usize> for Matrix4x1 {
-    type Output = f32;
+    type Output = Float;
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.values[index]
@@ -196,7 +201,7 @@ impl ops::Index<usize> for Matrix4x1 {
 }
 
 impl ops::Mul<Matrix4x1> for Matrix4x1 {
-    type Output = f32;
+    type Output = Float;
 
     fn mul(self, rhs: Matrix4x1) -> Self::Output {
         let v1 = self.values;
@@ -207,16 +212,17 @@ impl ops::Mul<Matrix4x1> for Matrix4x1 {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix4x4 {
-    values: [f32; 16],
+    values: [Float; 16],
 }
 
 impl Matrix4x4 {
-    pub fn new(values: [f32; 16]) -> Self {
+    pub fn new(values: [Float; 16]) -> Self {
         Self { values }
     }
 
-    pub fn from_2d_array(values: [[f32; 4]; 4]) -> Self {
+    pub fn from_2d_array(values: [[Float; 4]; 4]) -> Self {
         Self::new([
             values[0][0],
             values[0][1],
@@ -272,8 +278,8 @@ impl Matrix4x4 {
         ])
     }
 
-    pub fn determinant(&self) -> f32 {
-        let mut determinant: f32 = 0.0;
+    pub fn determinant(&self) -> Float {
+        let mut determinant: Float = 0.0;
 
         for c in 0..4 {
             let element = self[[0, c]];
@@ -285,11 +291,14 @@ impl Matrix4x4 {
         determinant
     }
 
+    /// False when the determinant is zero, or not a number at all
     pub fn invertible(&self) -> bool {
-        self.determinant() != 0.0
+        let determinant = self.determinant();
+
+        determinant != 0.0 && determinant.is_finite()
     }
 
-    pub fn cofactor(&self, row: usize, col: usize) -> f32 {
+    pub fn cofactor(&self, row: usize, col: usize) -> Float {
         let minor = self.minor(row, col);
 
         if (row + col) % 2 == 0 {
@@ -301,9 +310,9 @@ impl Matrix4x4 {
     }
 
     // Credit to https://www.mathsisfun.com/algebra/matrix-determinant.html for this optimization
-    pub fn minor(&self, row: usize, col: usize) -> f32 {
+    pub fn minor(&self, row: usize, col: usize) -> Float {
         let mut index = 0;
-        let mut m: [f32; 9] = [0.0; 9];
+        let mut m: [Float; 9] = [0.0; 9];
 
         for r in 0..=3 {
             if r == row {
@@ -348,22 +357,9 @@ impl Matrix4x4 {
         sub
     }
 
-    pub fn inverse(&self) -> Self {
-        if !self.invertible() {
-            panic!("Cannot invert a matrix with a determinant of zero");
-        }
-
-        let mut inverted = Matrix4x4::default();
-
-        for row_i in 0..4 {
-            for col_i in 0..4 {
-                let cofactor = self.cofactor(row_i, col_i);
-
-                inverted[[col_i, row_i]] = cofactor / self.determinant();
-            }
-        }
-
-        inverted
+    /// None when the matrix isn't invertible, such as a scaling by zero along some axis
+    pub fn inverse(&self) -> Option<Self> {
+        simd::inverse::<Float4>(self)
     }
 
     pub fn transposed(&self) -> Self {
@@ -387,7 +383,7 @@ impl Matrix4x4 {
 }
 
 impl ops::Index<[usize; 2]> for Matrix4x4 {
-    type Output = f32;
+    type Output = Float;
 
     fn index(&self, index: [usize; 2]) -> &Self::Output {
         let row = index[0];
@@ -410,17 +406,9 @@ impl ops::Mul<Point> for &Matrix4x4 {
     type Output = Point;
 
     fn mul(self, rhs: Point) -> Self::Output {
-        let rhs: [f32; 4] = [rhs.x(), rhs.y(), rhs.z(), 1.0]; // TODO: replace with Into<Matrix4x1>
-                                                              // TODO: These indices for Matrix4x4 may be wrong
-        let v = self.values;
-        let values: [f32; 4] = [
-            (v[0] * rhs[0]) + (v[1] * rhs[1]) + (v[2] * rhs[2]) + (v[3] * rhs[3]),
-            (v[4] * rhs[0]) + (v[5] * rhs[1]) + (v[6] * rhs[2]) + (v[7] * rhs[3]),
-            (v[8] * rhs[0]) + (v[9] * rhs[1]) + (v[10] * rhs[2]) + (v[11] * rhs[3]),
-            (v[12] * rhs[0]) + (v[13] * rhs[1]) + (v[14] * rhs[2]) + (v[15] * rhs[3]),
-        ];
+        let [x, y, z, _] = simd::transform::<Float4>(self, [rhs.x(), rhs.y(), rhs.z(), 1.0]);
 
-        Self::Output::new(values[0], values[1], values[2])
+        Self::Output::new(x, y, z)
     }
 }
 
@@ -428,16 +416,9 @@ impl ops::Mul<Vector> for &Matrix4x4 {
     type Output = Vector;
 
     fn mul(self, rhs: Vector) -> Self::Output {
-        let rhs: [f32; 4] = [rhs.x(), rhs.y(), rhs.z(), 0.0]; // TODO: replace with Into<Matrix4x1>
-        let v = self.values;
-        let values: [f32; 4] = [
-            (v[0] * rhs[0]) + (v[1] * rhs[1]) + (v[2] * rhs[2]) + (v[3] * rhs[3]),
-            (v[4] * rhs[0]) + (v[5] * rhs[1]) + (v[6] * rhs[2]) + (v[7] * rhs[3]),
-            (v[8] * rhs[0]) + (v[9] * rhs[1]) + (v[10] * rhs[2]) + (v[11] * rhs[3]),
-            (v[12] * rhs[0]) + (v[13] * rhs[1]) + (v[14] * rhs[2]) + (v[15] * rhs[3]),
-        ];
+        let [x, y, z, _] = simd::transform::<Float4>(self, [rhs.x(), rhs.y(), rhs.z(), 0.0]);
 
-        Self::Output::new(values[0], values[1], values[2])
+        Self::Output::new(x, y, z)
     }
 }
 
@@ -445,16 +426,7 @@ impl ops::Mul<Point> for Matrix4x4 {
     type Output = Point;
 
     fn mul(self, rhs: Point) -> Self::Output {
-        let rhs: [f32; 4] = [rhs.x(), rhs.y(), rhs.z(), 1.0]; // TODO: replace with Into<Matrix4x1>
-        let v = self.values;
-        let values: [f32; 4] = [
-            (v[0] * rhs[0]) + (v[1] * rhs[1]) + (v[2] * rhs[2]) + (v[3] * rhs[3]),
-            (v[4] * rhs[0]) + (v[5] * rhs[1]) + (v[6] * rhs[2]) + (v[7] * rhs[3]),
-            (v[8] * rhs[0]) + (v[9] * rhs[1]) + (v[10] * rhs[2]) + (v[11] * rhs[3]),
-            (v[12] * rhs[0]) + (v[13] * rhs[1]) + (v[14] * rhs[2]) + (v[15] * rhs[3]),
-        ];
-
-        Self::Output::new(values[0], values[1], values[2])
+        &self * rhs
     }
 }
 
@@ -462,15 +434,7 @@ impl ops::Mul<Matrix4x1> for Matrix4x4 {
     type Output = Matrix4x1;
 
     fn mul(self, rhs: Matrix4x1) -> Self::Output {
-        let v = self.values;
-        let values: [f32; 4] = [
-            (v[0] * rhs[0]) + (v[1] * rhs[1]) + (v[2] * rhs[2]) + (v[3] * rhs[3]),
-            (v[4] * rhs[0]) + (v[5] * rhs[1]) + (v[6] * rhs[2]) + (v[7] * rhs[3]),
-            (v[8] * rhs[0]) + (v[9] * rhs[1]) + (v[10] * rhs[2]) + (v[11] * rhs[3]),
-            (v[12] * rhs[0]) + (v[13] * rhs[1]) + (v[14] * rhs[2]) + (v[15] * rhs[3]),
-        ];
-
-        Self::Output::new(values)
+        Self::Output::new(simd::transform::<Float4>(&self, rhs.values))
     }
 }
 
@@ -478,19 +442,7 @@ impl ops::Mul<Matrix4x4> for Matrix4x4 {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        let mut values: [f32; 16] = [0.0; 16];
-
-        for ri in 0..4 {
-            let r = self.row(ri);
-
-            for ci in 0..4 {
-                let c = rhs.col(ci);
-
-                values[ri * 4 + ci] = r * c;
-            }
-        }
-
-        Self::Output::new(values)
+        simd::multiply::<Float4>(&self, &rhs)
     }
 }
 
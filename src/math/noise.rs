@@ -0,0 +1,146 @@
+use super::{Float, Point};
+
+// Ken Perlin's reference permutation from "Improving Noise" (2002)
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn perm(i: usize) -> usize {
+    PERMUTATION[i & 255] as usize
+}
+
+fn fade(t: Float) -> Float {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: Float, a: Float, b: Float) -> Float {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: Float, y: Float, z: Float) -> Float {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+
+    u + v
+}
+
+/// Improved Perlin noise. Returns a value in roughly [-1, 1] that varies smoothly with the point.
+pub fn perlin(point: Point) -> Float {
+    let (x, y, z) = (point.x(), point.y(), point.z());
+
+    let xi = (x.floor() as i32 & 255) as usize;
+    let yi = (y.floor() as i32 & 255) as usize;
+    let zi = (z.floor() as i32 & 255) as usize;
+
+    let x = x - x.floor();
+    let y = y - y.floor();
+    let z = z - z.floor();
+
+    let u = fade(x);
+    let v = fade(y);
+    let w = fade(z);
+
+    let a = perm(xi) + yi;
+    let aa = perm(a) + zi;
+    let ab = perm(a + 1) + zi;
+    let b = perm(xi + 1) + yi;
+    let ba = perm(b) + zi;
+    let bb = perm(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(perm(aa), x, y, z), grad(perm(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(perm(ab), x, y - 1.0, z),
+                grad(perm(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(perm(aa + 1), x, y, z - 1.0),
+                grad(perm(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(perm(ab + 1), x, y - 1.0, z - 1.0),
+                grad(perm(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+/// Fractal sum of `octaves` layers of Perlin noise, each at double the frequency and half the
+/// amplitude of the last.
+pub fn turbulence(point: Point, octaves: u32) -> Float {
+    let mut sum = 0.0;
+    let mut frequency = 1.0;
+    let mut amplitude = 1.0;
+
+    for _ in 0..octaves {
+        sum += perlin(point * frequency) * amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_zero_on_lattice_points() {
+        for &(x, y, z) in &[(0.0, 0.0, 0.0), (1.0, 2.0, 3.0), (-4.0, 7.0, -2.0)] {
+            let n = perlin(Point::new(x, y, z));
+            assert!(
+                n.abs() < 1.0e-6,
+                "Expected 0 at ({}, {}, {}) but got {}",
+                x,
+                y,
+                z,
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_bounded() {
+        let p = Point::new(1.3, -2.7, 0.45);
+
+        assert_eq!(perlin(p), perlin(p));
+
+        for i in 0..100 {
+            let t = i as Float * 0.173;
+            let n = perlin(Point::new(t, t * 0.5, -t));
+            assert!(n.abs() <= 1.1, "Noise out of range: {}", n);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::ops;
+
+use super::transforms::TransformationMatrix;
+use super::{Float, Point, Vector};
+use crate::CENTER_ORIGIN;
+
+// Below this angle between two rotations, slerp blends them linearly instead
+const SLERP_THRESHOLD: Float = 0.9995;
+
+/// A rotation as `w + xi + yj + zk`. Unlike the axis rotations in `transforms`, quaternions
+/// don't gimbal lock and can be blended smoothly with `slerp`. Rotating with one assumes it's
+/// normalized, which everything here except `new` takes care of.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion {
+    pub w: Float,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
+}
+
+impl Quaternion {
+    pub fn new(w: Float, x: Float, y: Float, z: Float) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// No rotation at all
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Rotation by `radians` about `axis`, which needn't be normalized. Turns the same way as
+    /// `transforms::rotation`.
+    pub fn from_axis_angle(axis: &Vector, radians: Float) -> Self {
+        let axis = axis.norm();
+        let half = radians / 2.0;
+        let s = half.sin();
+
+        Self::new(half.cos(), axis.x * s, axis.y * s, axis.z * s)
+    }
+
+    /// Rotation by `x` radians about the x axis, then `y` about the y axis, then `z` about the z
+    /// axis, the same as `rotation_z(z) * rotation_y(y) * rotation_x(x)`
+    pub fn from_euler(x: Float, y: Float, z: Float) -> Self {
+        let about_x = Self::from_axis_angle(&Vector::new(1.0, 0.0, 0.0), x);
+        let about_y = Self::from_axis_angle(&Vector::new(0.0, 1.0, 0.0), y);
+        let about_z = Self::from_axis_angle(&Vector::new(0.0, 0.0, 1.0), z);
+
+        about_z * about_y * about_x
+    }
+
+    pub fn dot(&self, other: &Self) -> Float {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn mag(&self) -> Float {
+        self.dot(self).sqrt()
+    }
+
+    pub fn norm(self) -> Self {
+        let mag = self.mag();
+
+        Self::new(self.w / mag, self.x / mag, self.y / mag, self.z / mag)
+    }
+
+    /// The opposite rotation, for a normalized quaternion
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Rotation `t` of the way from `self` to `other`, at a steady speed and along the shortest
+    /// way around
+    pub fn slerp(&self, other: &Self, t: Float) -> Self {
+        let mut other = *other;
+        let mut cos_theta = self.dot(&other);
+
+        // q and -q are the same rotation, but only one of them is the short way round
+        if cos_theta < 0.0 {
+            other = Self::new(-other.w, -other.x, -other.y, -other.z);
+            cos_theta = -cos_theta;
+        }
+
+        let (from, to) = if cos_theta > SLERP_THRESHOLD {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+
+        Self::new(
+            self.w * from + other.w * to,
+            self.x * from + other.x * to,
+            self.y * from + other.y * to,
+            self.z * from + other.z * to,
+        )
+        .norm()
+    }
+
+    pub fn to_matrix(&self) -> TransformationMatrix {
+        let Self { w, x, y, z } = *self;
+
+        TransformationMatrix::from_2d_array([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl From<Quaternion> for TransformationMatrix {
+    fn from(rotation: Quaternion) -> Self {
+        rotation.to_matrix()
+    }
+}
+
+/// The rotation `rhs`, followed by `self`
+impl ops::Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        Self::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+impl ops::Mul<Vector> for Quaternion {
+    type Output = Vector;
+
+    fn mul(self, rhs: Vector) -> Self::Output {
+        let axis = Vector::new(self.x, self.y, self.z);
+        let t = axis.cross(rhs) * 2.0;
+
+        rhs + t * self.w + axis.cross(t)
+    }
+}
+
+/// Rotates the point about the origin
+impl ops::Mul<Point> for Quaternion {
+    type Output = Point;
+
+    fn mul(self, rhs: Point) -> Self::Output {
+        CENTER_ORIGIN + self * (rhs - CENTER_ORIGIN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::equalish;
+    use crate::math::transforms::{rotation, rotation_x, rotation_y, rotation_z};
+
+    fn assert_matrix_equalish(expected: TransformationMatrix, actual: TransformationMatrix) {
+        for r in 0..4 {
+            for c in 0..4 {
+                assert!(
+                    equalish(expected[[r, c]], actual[[r, c]]),
+                    "Expected {:?}, got {:?}",
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn axis_angle_matches_the_rotation_matrix() {
+        let axis = Vector::new(1.0, 2.0, -0.5);
+        let q = Quaternion::from_axis_angle(&axis, 1.1);
+
+        assert!(equalish(1.0, q.mag()));
+        assert_matrix_equalish(rotation(&axis, 1.1), q.to_matrix());
+    }
+
+    #[test]
+    fn euler_angles_apply_x_then_y_then_z() {
+        let q = Quaternion::from_euler(0.3, -0.5, 1.2);
+
+        assert_matrix_equalish(
+            rotation_z(1.2) * rotation_y(-0.5) * rotation_x(0.3),
+            TransformationMatrix::from(q),
+        );
+    }
+
+    #[test]
+    fn multiplying_applies_the_right_hand_side_first() {
+        let a = Quaternion::from_axis_angle(&Vector::new(1.0, 0.0, 0.0), PI / 2.0);
+        let b = Quaternion::from_axis_angle(&Vector::new(0.0, 0.0, 1.0), PI / 2.0);
+
+        assert_matrix_equalish(a.to_matrix() * b.to_matrix(), (a * b).to_matrix());
+        assert_eq!(
+            Quaternion::identity(),
+            Quaternion::identity() * Quaternion::identity()
+        );
+    }
+
+    #[test]
+    fn rotating_points_and_vectors() {
+        let quarter_turn_x = Quaternion::from_axis_angle(&Vector::new(1.0, 0.0, 0.0), PI / 2.0);
+
+        assert!(
+            (quarter_turn_x * Point::new(0.0, 1.0, 0.0)).equalish_to(&Point::new(0.0, 0.0, 1.0))
+        );
+        assert!(
+            (quarter_turn_x * Vector::new(0.0, 0.0, 1.0)).equalish_to(&Vector::new(0.0, -1.0, 0.0))
+        );
+
+        let q = Quaternion::from_euler(0.4, 0.9, -2.0);
+        let v = Vector::new(1.0, -2.0, 3.0);
+
+        assert!((q * v).equalish_to(&(&q.to_matrix() * v)));
+        assert!((q.conjugate() * (q * v)).equalish_to(&v));
+    }
+
+    #[test]
+    fn normalizing_gives_a_unit_quaternion() {
+        let q = Quaternion::new(2.0, 0.0, 0.0, 2.0).norm();
+
+        assert!(equalish(1.0, q.mag()));
+        assert!(equalish((0.5 as Float).sqrt(), q.w));
+    }
+
+    #[test]
+    fn slerp_moves_at_a_steady_angle() {
+        let axis = Vector::new(0.0, 1.0, 0.0);
+        let start = Quaternion::identity();
+        let end = Quaternion::from_axis_angle(&axis, PI / 2.0);
+
+        assert_eq!(start, start.slerp(&end, 0.0));
+        assert_matrix_equalish(end.to_matrix(), start.slerp(&end, 1.0).to_matrix());
+        assert_matrix_equalish(
+            Quaternion::from_axis_angle(&axis, PI / 6.0).to_matrix(),
+            start.slerp(&end, 1.0 / 3.0).to_matrix(),
+        );
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way_round() {
+        let axis = Vector::new(0.0, 0.0, 1.0);
+        let start = Quaternion::from_axis_angle(&axis, -0.2);
+        // 0.2 radians the other way, written as a turn of almost a full circle
+        let end = Quaternion::from_axis_angle(&axis, 0.2 - 2.0 * PI);
+
+        let halfway = start.slerp(&end, 0.5);
+
+        assert_matrix_equalish(TransformationMatrix::identity(), halfway.to_matrix());
+    }
+}
@@ -0,0 +1,503 @@
+//! Four lanes at a time math behind `Matrix4x4`. x86_64 builds with f32 use SSE, which every
+//! x86_64 CPU has. Everything else, including f64 builds, uses `scalar::Float4`, which does the
+//! same arithmetic in the same order, so both give bit-identical results.
+//!
+//! The matrix functions are generic over `Lanes` so either version can be picked explicitly, for
+//! testing and benchmarking. `Float4` is the one `Matrix4x4` uses.
+
+use std::ops;
+
+use super::Matrix4x4;
+use crate::Float;
+
+#[cfg(not(all(target_arch = "x86_64", not(feature = "f64"))))]
+pub use self::scalar::Float4;
+#[cfg(all(target_arch = "x86_64", not(feature = "f64")))]
+pub use self::sse::Float4;
+
+/// Four floats operated on together, lane by lane
+pub trait Lanes:
+    Copy
+    + ops::Add<Output = Self>
+    + ops::Sub<Output = Self>
+    + ops::Mul<Output = Self>
+    + ops::Div<Output = Self>
+{
+    fn new(x: Float, y: Float, z: Float, w: Float) -> Self;
+
+    /// All four lanes set to `value`
+    fn splat(value: Float) -> Self;
+
+    fn load(values: &[Float; 4]) -> Self;
+
+    fn to_array(self) -> [Float; 4];
+
+    /// Lanes in the order y, z, x, w
+    fn yzxw(self) -> Self;
+
+    /// Lanes in the order z, x, y, w
+    fn zxyw(self) -> Self;
+
+    fn sqrt(self) -> Self;
+
+    /// The smaller of each pair of lanes. Like SSE, this is `rhs` if either is NaN.
+    fn min(self, rhs: Self) -> Self;
+
+    /// The larger of each pair of lanes. Like SSE, this is `rhs` if either is NaN.
+    fn max(self, rhs: Self) -> Self;
+
+    /// Cross product of the x, y and z lanes. The w lane is zero for finite inputs.
+    #[inline]
+    fn cross(self, rhs: Self) -> Self {
+        self.yzxw() * rhs.zxyw() - self.zxyw() * rhs.yzxw()
+    }
+
+    /// Dot product of the x, y and z lanes, summed in the same order as `Vector::dot`
+    #[inline]
+    fn dot3(self, rhs: Self) -> Float {
+        let p = (self * rhs).to_array();
+
+        p[0] + p[1] + p[2]
+    }
+
+    #[inline]
+    fn dot4(self, rhs: Self) -> Float {
+        let p = (self * rhs).to_array();
+
+        p[0] + p[1] + p[2] + p[3]
+    }
+}
+
+fn row<L: Lanes>(m: &Matrix4x4, r: usize) -> L {
+    let v = &m.values;
+
+    L::load(&[v[r * 4], v[r * 4 + 1], v[r * 4 + 2], v[r * 4 + 3]])
+}
+
+fn column<L: Lanes>(m: &Matrix4x4, c: usize) -> L {
+    let v = &m.values;
+
+    L::new(v[c], v[4 + c], v[8 + c], v[12 + c])
+}
+
+/// `a * b`. Each row of the result is built up one row of `b` at a time.
+pub fn multiply<L: Lanes>(a: &Matrix4x4, b: &Matrix4x4) -> Matrix4x4 {
+    let b_rows: [L; 4] = [row(b, 0), row(b, 1), row(b, 2), row(b, 3)];
+    let mut values = [0.0; 16];
+
+    for r in 0..4 {
+        let a_row = &a.values[r * 4..r * 4 + 4];
+
+        let product = L::splat(a_row[0]) * b_rows[0]
+            + L::splat(a_row[1]) * b_rows[1]
+            + L::splat(a_row[2]) * b_rows[2]
+            + L::splat(a_row[3]) * b_rows[3];
+
+        values[r * 4..r * 4 + 4].copy_from_slice(&product.to_array());
+    }
+
+    Matrix4x4::new(values)
+}
+
+/// `m * [x, y, z, w]`, built up one column of `m` at a time
+pub fn transform<L: Lanes>(m: &Matrix4x4, xyzw: [Float; 4]) -> [Float; 4] {
+    let product = column::<L>(m, 0) * L::splat(xyzw[0])
+        + column::<L>(m, 1) * L::splat(xyzw[1])
+        + column::<L>(m, 2) * L::splat(xyzw[2])
+        + column::<L>(m, 3) * L::splat(xyzw[3]);
+
+    product.to_array()
+}
+
+/// Inverse by cofactor expansion, like `Matrix4x4::cofactor` but working out the four cofactors
+/// of a row at once. The arithmetic is the same, so the results match exactly. None if the
+/// matrix isn't invertible.
+pub fn inverse<L: Lanes>(m: &Matrix4x4) -> Option<Matrix4x4> {
+    let cofactors: [L; 4] = [
+        row_cofactors(m, 0),
+        row_cofactors(m, 1),
+        row_cofactors(m, 2),
+        row_cofactors(m, 3),
+    ];
+
+    let determinant = cofactors[0].dot4(row(m, 0));
+
+    if determinant == 0.0 || !determinant.is_finite() {
+        return None;
+    }
+
+    let mut inverted = Matrix4x4::default();
+
+    for (r, cofactors) in cofactors.iter().enumerate() {
+        let column = (*cofactors / L::splat(determinant)).to_array();
+
+        for (c, value) in column.iter().enumerate() {
+            inverted[[c, r]] = *value;
+        }
+    }
+
+    Some(inverted)
+}
+
+// Cofactors of every element in row `r`, one per lane
+fn row_cofactors<L: Lanes>(m: &Matrix4x4, r: usize) -> L {
+    // Lane c of each is the first, second and third element of a row once column c is removed
+    let remaining = |row: usize| {
+        let v = &m.values[row * 4..row * 4 + 4];
+
+        (
+            L::new(v[1], v[0], v[0], v[0]),
+            L::new(v[2], v[2], v[1], v[1]),
+            L::new(v[3], v[3], v[3], v[2]),
+        )
+    };
+
+    let mut rows = (0..4).filter(|&row| row != r).map(remaining);
+    let (m0, m1, m2) = rows.next().unwrap();
+    let (m3, m4, m5) = rows.next().unwrap();
+    let (m6, m7, m8) = rows.next().unwrap();
+
+    let minors = m0 * (m4 * m8 - m5 * m7) - m1 * (m3 * m8 - m5 * m6) + m2 * (m3 * m7 - m4 * m6);
+
+    // Checkerboard of signs, starting with + on even rows
+    let signs = match r {
+        0 | 2 => L::new(1.0, -1.0, 1.0, -1.0),
+        _ => L::new(-1.0, 1.0, -1.0, 1.0),
+    };
+
+    minors * signs
+}
+
+pub mod scalar {
+    use super::*;
+
+    /// Plain array version of the lanes, for when there's no SIMD to use
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Float4([Float; 4]);
+
+    impl Lanes for Float4 {
+        #[inline]
+        fn new(x: Float, y: Float, z: Float, w: Float) -> Self {
+            Float4([x, y, z, w])
+        }
+
+        #[inline]
+        fn splat(value: Float) -> Self {
+            Float4([value; 4])
+        }
+
+        #[inline]
+        fn load(values: &[Float; 4]) -> Self {
+            Float4(*values)
+        }
+
+        #[inline]
+        fn to_array(self) -> [Float; 4] {
+            self.0
+        }
+
+        #[inline]
+        fn yzxw(self) -> Self {
+            let [x, y, z, w] = self.0;
+
+            Float4([y, z, x, w])
+        }
+
+        #[inline]
+        fn zxyw(self) -> Self {
+            let [x, y, z, w] = self.0;
+
+            Float4([z, x, y, w])
+        }
+
+        #[inline]
+        fn sqrt(self) -> Self {
+            let [x, y, z, w] = self.0;
+
+            Float4([x.sqrt(), y.sqrt(), z.sqrt(), w.sqrt()])
+        }
+
+        #[inline]
+        fn min(self, rhs: Self) -> Self {
+            let (a, b) = (self.0, rhs.0);
+            let min = |a: Float, b: Float| if a < b { a } else { b };
+
+            Float4([
+                min(a[0], b[0]),
+                min(a[1], b[1]),
+                min(a[2], b[2]),
+                min(a[3], b[3]),
+            ])
+        }
+
+        #[inline]
+        fn max(self, rhs: Self) -> Self {
+            let (a, b) = (self.0, rhs.0);
+            let max = |a: Float, b: Float| if a > b { a } else { b };
+
+            Float4([
+                max(a[0], b[0]),
+                max(a[1], b[1]),
+                max(a[2], b[2]),
+                max(a[3], b[3]),
+            ])
+        }
+    }
+
+    impl ops::Add for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn add(self, rhs: Self) -> Self::Output {
+            let (a, b) = (self.0, rhs.0);
+
+            Float4([a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]])
+        }
+    }
+
+    impl ops::Sub for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn sub(self, rhs: Self) -> Self::Output {
+            let (a, b) = (self.0, rhs.0);
+
+            Float4([a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]])
+        }
+    }
+
+    impl ops::Mul for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn mul(self, rhs: Self) -> Self::Output {
+            let (a, b) = (self.0, rhs.0);
+
+            Float4([a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]])
+        }
+    }
+
+    impl ops::Div for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn div(self, rhs: Self) -> Self::Output {
+            let (a, b) = (self.0, rhs.0);
+
+            Float4([a[0] / b[0], a[1] / b[1], a[2] / b[2], a[3] / b[3]])
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "f64")))]
+pub mod sse {
+    use std::arch::x86_64::*;
+
+    use super::*;
+
+    // Shuffle masks picking lanes (1, 2, 0, 3) and (2, 0, 1, 3)
+    const YZXW: i32 = 0b11_00_10_01;
+    const ZXYW: i32 = 0b11_01_00_10;
+
+    // The intrinsics are only unsafe because they need SSE, which x86_64 always has
+
+    /// The lanes in an SSE register
+    #[derive(Copy, Clone, Debug)]
+    pub struct Float4(__m128);
+
+    impl Lanes for Float4 {
+        #[inline]
+        fn new(x: Float, y: Float, z: Float, w: Float) -> Self {
+            Float4(unsafe { _mm_set_ps(w, z, y, x) })
+        }
+
+        #[inline]
+        fn splat(value: Float) -> Self {
+            Float4(unsafe { _mm_set1_ps(value) })
+        }
+
+        #[inline]
+        fn load(values: &[Float; 4]) -> Self {
+            Float4(unsafe { _mm_loadu_ps(values.as_ptr()) })
+        }
+
+        #[inline]
+        fn to_array(self) -> [Float; 4] {
+            let mut values = [0.0; 4];
+
+            unsafe { _mm_storeu_ps(values.as_mut_ptr(), self.0) };
+
+            values
+        }
+
+        #[inline]
+        fn yzxw(self) -> Self {
+            Float4(unsafe { _mm_shuffle_ps::<YZXW>(self.0, self.0) })
+        }
+
+        #[inline]
+        fn zxyw(self) -> Self {
+            Float4(unsafe { _mm_shuffle_ps::<ZXYW>(self.0, self.0) })
+        }
+
+        #[inline]
+        fn sqrt(self) -> Self {
+            Float4(unsafe { _mm_sqrt_ps(self.0) })
+        }
+
+        #[inline]
+        fn min(self, rhs: Self) -> Self {
+            Float4(unsafe { _mm_min_ps(self.0, rhs.0) })
+        }
+
+        #[inline]
+        fn max(self, rhs: Self) -> Self {
+            Float4(unsafe { _mm_max_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Add for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn add(self, rhs: Self) -> Self::Output {
+            Float4(unsafe { _mm_add_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Sub for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn sub(self, rhs: Self) -> Self::Output {
+            Float4(unsafe { _mm_sub_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Mul for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn mul(self, rhs: Self) -> Self::Output {
+            Float4(unsafe { _mm_mul_ps(self.0, rhs.0) })
+        }
+    }
+
+    impl ops::Div for Float4 {
+        type Output = Self;
+
+        #[inline]
+        fn div(self, rhs: Self) -> Self::Output {
+            Float4(unsafe { _mm_div_ps(self.0, rhs.0) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::transforms::{rotation_y, scaling, shearing, translation};
+    use crate::Vector;
+
+    fn matrices() -> Vec<Matrix4x4> {
+        vec![
+            Matrix4x4::identity(),
+            translation(1.0, -2.0, 3.5) * rotation_y(0.7) * scaling(2.0, 0.5, 3.0),
+            shearing(1.0, 0.0, 0.3, 0.0, -2.0, 1.0) * translation(0.25, 8.0, -1.0),
+            Matrix4x4::from_2d_array([
+                [-5.0, 2.0, 6.0, -8.0],
+                [1.0, -5.0, 1.0, 8.0],
+                [7.0, 7.0, -6.0, -7.0],
+                [1.0, -3.0, 7.0, 4.0],
+            ]),
+        ]
+    }
+
+    // The inverse the slow way, one cofactor at a time
+    fn cofactor_inverse(m: &Matrix4x4) -> Matrix4x4 {
+        let mut inverted = Matrix4x4::default();
+
+        for r in 0..4 {
+            for c in 0..4 {
+                inverted[[c, r]] = m.cofactor(r, c) / m.determinant();
+            }
+        }
+
+        inverted
+    }
+
+    #[test]
+    fn lanes_match_scalar_dot_and_cross() {
+        let (a, b) = (Vector::new(1.5, -2.0, 3.0), Vector::new(0.25, 4.0, -1.0));
+        let (la, lb) = (
+            Float4::new(a.x, a.y, a.z, 0.0),
+            Float4::new(b.x, b.y, b.z, 0.0),
+        );
+
+        let cross = la.cross(lb).to_array();
+
+        // The plain scalar versions, which the lanes have to match exactly
+        assert_eq!(a.x * b.x + a.y * b.y + a.z * b.z, la.dot3(lb));
+        assert_eq!(
+            [
+                a.y * b.z - a.z * b.y,
+                a.z * b.x - a.x * b.z,
+                a.x * b.y - a.y * b.x
+            ],
+            [cross[0], cross[1], cross[2]]
+        );
+        assert_eq!(a.cross(b), Vector::new(cross[0], cross[1], cross[2]));
+        assert_eq!(0.0, cross[3]);
+        assert_eq!(
+            2.0,
+            Float4::splat(1.0).dot4(Float4::new(1.0, 2.0, 3.0, -4.0))
+        );
+    }
+
+    #[test]
+    fn inverse_matches_the_cofactor_inverse_exactly() {
+        for m in matrices() {
+            assert_eq!(Some(cofactor_inverse(&m)), inverse::<Float4>(&m));
+        }
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert_eq!(None, inverse::<Float4>(&scaling(1.0, 0.0, 1.0)));
+        assert_eq!(None, inverse::<Float4>(&Matrix4x4::default()));
+        assert_eq!(None, inverse::<Float4>(&scaling(Float::NAN, 1.0, 1.0)));
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(feature = "f64")))]
+    #[test]
+    fn sse_and_scalar_are_bit_identical() {
+        for a in matrices() {
+            for b in matrices() {
+                assert_eq!(
+                    multiply::<scalar::Float4>(&a, &b),
+                    multiply::<sse::Float4>(&a, &b)
+                );
+            }
+
+            let xyzw = [0.1, -7.0, 3.3, 1.0];
+
+            assert_eq!(
+                transform::<scalar::Float4>(&a, xyzw),
+                transform::<sse::Float4>(&a, xyzw)
+            );
+            assert_eq!(inverse::<scalar::Float4>(&a), inverse::<sse::Float4>(&a));
+        }
+
+        // min and max pick the same side as SSE when either is NaN or the zeros differ in sign
+        let (a, b) = ([2.0, -0.0, Float::NAN, 1.0], [0.5, 0.0, 3.0, Float::NAN]);
+        let (sa, sb) = (scalar::Float4::load(&a), scalar::Float4::load(&b));
+        let (ea, eb) = (sse::Float4::load(&a), sse::Float4::load(&b));
+
+        // Compared as text, since NaN isn't equal to itself
+        let text = |lanes: [Float; 4]| format!("{:?}", lanes);
+
+        assert_eq!(text(sa.min(sb).to_array()), text(ea.min(eb).to_array()));
+        assert_eq!(text(sa.max(sb).to_array()), text(ea.max(eb).to_array()));
+        assert_eq!(text(sa.sqrt().to_array()), text(ea.sqrt().to_array()));
+    }
+}
@@ -1,8 +1,92 @@
-use super::{Matrix4x4, Point, Vector};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use super::{Float, Matrix4x4, Point, Vector};
+use crate::EPSILON;
 
 pub type TransformationMatrix = Matrix4x4;
 
-pub fn translation(x: f32, y: f32, z: f32) -> TransformationMatrix {
+/// A transform that can't be undone, such as a scaling by zero, so nothing could be intersected
+/// with whatever it's applied to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SingularTransform;
+
+impl fmt::Display for SingularTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "transform can't be inverted (it flattens space, e.g. by scaling by zero)"
+        )
+    }
+}
+
+impl Error for SingularTransform {}
+
+/// A transformation matrix together with its inverse and inverse transpose, worked out once when
+/// the transform is set instead of on every ray. Serialized as just the matrix.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "TransformationMatrix", into = "TransformationMatrix")
+)]
+pub struct Transform {
+    matrix: TransformationMatrix,
+    inverse: TransformationMatrix,
+    inverse_transpose: TransformationMatrix,
+}
+
+impl Transform {
+    pub fn new(matrix: TransformationMatrix) -> Result<Self, SingularTransform> {
+        let inverse = matrix.inverse().ok_or(SingularTransform)?;
+
+        Ok(Self {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transposed(),
+        })
+    }
+
+    pub fn identity() -> Self {
+        let identity = TransformationMatrix::identity();
+
+        Self {
+            matrix: identity,
+            inverse: identity,
+            inverse_transpose: identity,
+        }
+    }
+
+    pub fn matrix(&self) -> TransformationMatrix {
+        self.matrix
+    }
+
+    pub fn inverse(&self) -> TransformationMatrix {
+        self.inverse
+    }
+
+    /// Takes object space normals to world space
+    pub fn inverse_transpose(&self) -> TransformationMatrix {
+        self.inverse_transpose
+    }
+}
+
+impl TryFrom<TransformationMatrix> for Transform {
+    type Error = SingularTransform;
+
+    fn try_from(matrix: TransformationMatrix) -> Result<Self, Self::Error> {
+        Self::new(matrix)
+    }
+}
+
+impl From<Transform> for TransformationMatrix {
+    fn from(transform: Transform) -> Self {
+        transform.matrix
+    }
+}
+
+pub fn translation(x: Float, y: Float, z: Float) -> TransformationMatrix {
     TransformationMatrix::from_2d_array([
         [1.0, 0.0, 0.0, x],
         [0.0, 1.0, 0.0, y],
@@ -11,7 +95,7 @@ pub fn translation(x: f32, y: f32, z: f32) -> TransformationMatrix {
     ])
 }
 
-pub fn scaling(x: f32, y: f32, z: f32) -> TransformationMatrix {
+pub fn scaling(x: Float, y: Float, z: Float) -> TransformationMatrix {
     TransformationMatrix::from_2d_array([
         [x, 0.0, 0.0, 0.0],
         [0.0, y, 0.0, 0.0],
@@ -20,53 +104,78 @@ pub fn scaling(x: f32, y: f32, z: f32) -> TransformationMatrix {
     ])
 }
 
-pub fn rotation_x(radians: f32) -> TransformationMatrix {
+pub fn rotation_x(radians: Float) -> TransformationMatrix {
     let cr = radians.cos();
     let sr = radians.sin();
 
-    let r1: [f32; 4] = [1.0, 0.0, 0.0, 0.0];
-    let r2: [f32; 4] = [0.0, cr, -sr, 0.0];
-    let r3: [f32; 4] = [0.0, sr, cr, 0.0];
-    let r4: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    let r1: [Float; 4] = [1.0, 0.0, 0.0, 0.0];
+    let r2: [Float; 4] = [0.0, cr, -sr, 0.0];
+    let r3: [Float; 4] = [0.0, sr, cr, 0.0];
+    let r4: [Float; 4] = [0.0, 0.0, 0.0, 1.0];
 
     TransformationMatrix::from_2d_array([r1, r2, r3, r4])
 }
 
-pub fn rotation_y(radians: f32) -> TransformationMatrix {
+pub fn rotation_y(radians: Float) -> TransformationMatrix {
     let cr = radians.cos();
     let sr = radians.sin();
 
-    let r1: [f32; 4] = [cr, 0.0, sr, 0.0];
-    let r2: [f32; 4] = [0.0, 1.0, 0.0, 0.0];
-    let r3: [f32; 4] = [-sr, 0.0, cr, 0.0];
-    let r4: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    let r1: [Float; 4] = [cr, 0.0, sr, 0.0];
+    let r2: [Float; 4] = [0.0, 1.0, 0.0, 0.0];
+    let r3: [Float; 4] = [-sr, 0.0, cr, 0.0];
+    let r4: [Float; 4] = [0.0, 0.0, 0.0, 1.0];
 
     TransformationMatrix::from_2d_array([r1, r2, r3, r4])
 }
 
-pub fn rotation_z(radians: f32) -> TransformationMatrix {
+pub fn rotation_z(radians: Float) -> TransformationMatrix {
     let cr = radians.cos();
     let sr = radians.sin();
 
-    let r1: [f32; 4] = [cr, -sr, 0.0, 0.0];
-    let r2: [f32; 4] = [sr, cr, 0.0, 0.0];
-    let r3: [f32; 4] = [0.0, 0.0, 1.0, 0.0];
-    let r4: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    let r1: [Float; 4] = [cr, -sr, 0.0, 0.0];
+    let r2: [Float; 4] = [sr, cr, 0.0, 0.0];
+    let r3: [Float; 4] = [0.0, 0.0, 1.0, 0.0];
+    let r4: [Float; 4] = [0.0, 0.0, 0.0, 1.0];
 
     TransformationMatrix::from_2d_array([r1, r2, r3, r4])
 }
 
-pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> TransformationMatrix {
-    let r1: [f32; 4] = [1.0, xy, xz, 0.0];
-    let r2: [f32; 4] = [yx, 1.0, yz, 0.0];
-    let r3: [f32; 4] = [zx, zy, 1.0, 0.0];
-    let r4: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+/// Rotation by `radians` about `axis`, which needn't be normalized. Turns the same way as
+/// `rotation_x` and friends when `axis` is one of the axes.
+pub fn rotation(axis: &Vector, radians: Float) -> TransformationMatrix {
+    let axis = axis.norm();
+    let (x, y, z) = (axis.x, axis.y, axis.z);
+
+    let c = radians.cos();
+    let s = radians.sin();
+    let t = 1.0 - c;
+
+    let r1: [Float; 4] = [c + x * x * t, x * y * t - z * s, x * z * t + y * s, 0.0];
+    let r2: [Float; 4] = [y * x * t + z * s, c + y * y * t, y * z * t - x * s, 0.0];
+    let r3: [Float; 4] = [z * x * t - y * s, z * y * t + x * s, c + z * z * t, 0.0];
+    let r4: [Float; 4] = [0.0, 0.0, 0.0, 1.0];
+
+    TransformationMatrix::from_2d_array([r1, r2, r3, r4])
+}
+
+pub fn shearing(
+    xy: Float,
+    xz: Float,
+    yx: Float,
+    yz: Float,
+    zx: Float,
+    zy: Float,
+) -> TransformationMatrix {
+    let r1: [Float; 4] = [1.0, xy, xz, 0.0];
+    let r2: [Float; 4] = [yx, 1.0, yz, 0.0];
+    let r3: [Float; 4] = [zx, zy, 1.0, 0.0];
+    let r4: [Float; 4] = [0.0, 0.0, 0.0, 1.0];
 
     TransformationMatrix::from_2d_array([r1, r2, r3, r4])
 }
 
 pub fn view_transform(from: &Point, to: &Point, up: &Vector) -> TransformationMatrix {
-    // TODO: Implement #norm for Array<f32, Ix1> so that we don't have to convert types
+    // TODO: Implement #norm for Array<Float, Ix1> so that we don't have to convert types
     let forward: Vector = to.clone() - from.clone();
     let forward = forward.norm();
 
@@ -74,12 +183,192 @@ pub fn view_transform(from: &Point, to: &Point, up: &Vector) -> TransformationMa
 
     let true_up = left.cross(forward.clone());
 
-    let r1: [f32; 4] = [left.x, left.y, left.z, 0.0];
-    let r2: [f32; 4] = [true_up.x, true_up.y, true_up.z, 0.0];
-    let r3: [f32; 4] = [-forward.x, -forward.y, -forward.z, 0.0];
-    let r4: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    let r1: [Float; 4] = [left.x, left.y, left.z, 0.0];
+    let r2: [Float; 4] = [true_up.x, true_up.y, true_up.z, 0.0];
+    let r3: [Float; 4] = [-forward.x, -forward.y, -forward.z, 0.0];
+    let r4: [Float; 4] = [0.0, 0.0, 0.0, 1.0];
 
     let orientation = TransformationMatrix::from_2d_array([r1, r2, r3, r4]);
 
     orientation * translation(-from.x, -from.y, -from.z)
 }
+
+/// Chainable versions of the functions above. Each one applies after everything before it, so
+/// `identity().rotate_x(a).scale(2.0, 2.0, 2.0).translate(x, y, z)` rotates, then scales, then
+/// translates, where the plain functions have to be multiplied the other way around.
+impl TransformationMatrix {
+    pub fn translate(self, x: Float, y: Float, z: Float) -> Self {
+        translation(x, y, z) * self
+    }
+
+    pub fn scale(self, x: Float, y: Float, z: Float) -> Self {
+        scaling(x, y, z) * self
+    }
+
+    pub fn rotate_x(self, radians: Float) -> Self {
+        rotation_x(radians) * self
+    }
+
+    pub fn rotate_y(self, radians: Float) -> Self {
+        rotation_y(radians) * self
+    }
+
+    pub fn rotate_z(self, radians: Float) -> Self {
+        rotation_z(radians) * self
+    }
+
+    pub fn rotate(self, axis: &Vector, radians: Float) -> Self {
+        rotation(axis, radians) * self
+    }
+
+    pub fn shear(self, xy: Float, xz: Float, yx: Float, yz: Float, zx: Float, zy: Float) -> Self {
+        shearing(xy, xz, yx, yz, zx, zy) * self
+    }
+
+    /// Splits the transform into a scale, then a rotation, then a translation. Shearing can't be
+    /// represented and is lost. None if the transform scales anything to nothing.
+    pub fn decompose(&self) -> Option<Decomposition> {
+        if !self.invertible() {
+            return None;
+        }
+
+        let column = |c: usize| Vector::new(self[[0, c]], self[[1, c]], self[[2, c]]);
+        let (mut x_axis, y_axis, z_axis) = (column(0), column(1), column(2));
+
+        let mut scale = Vector::new(x_axis.mag(), y_axis.mag(), z_axis.mag());
+
+        // A mirror image can't be made by rotating, so put the flip in the scale
+        if x_axis.cross(y_axis).dot(z_axis) < 0.0 {
+            scale.x = -scale.x;
+            x_axis = x_axis * -1.0;
+        }
+
+        let (x_axis, y_axis, z_axis) = (
+            x_axis * (1.0 / x_axis.mag()),
+            y_axis * (1.0 / y_axis.mag()),
+            z_axis * (1.0 / z_axis.mag()),
+        );
+
+        // Rotation is rotation_z(c) * rotation_y(b) * rotation_x(a)
+        let b = (-x_axis.z).clamp(-1.0, 1.0).asin();
+        let (a, c) = if b.cos().abs() > EPSILON {
+            (y_axis.z.atan2(z_axis.z), x_axis.y.atan2(x_axis.x))
+        } else {
+            // Looking straight up or down the y axis, where x and z rotations do the same thing
+            ((-z_axis.y).atan2(y_axis.y), 0.0)
+        };
+
+        Some(Decomposition {
+            translation: Vector::new(self[[0, 3]], self[[1, 3]], self[[2, 3]]),
+            rotation: Vector::new(a, b, c),
+            scale,
+        })
+    }
+}
+
+/// A transform in parts, as they'd be passed to `scaling`, `rotation_x`, `rotation_y`,
+/// `rotation_z` and `translation`. The parts are applied in that order.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decomposition {
+    pub translation: Vector,
+    /// Radians about the x, then y, then z axis
+    pub rotation: Vector,
+    pub scale: Vector,
+}
+
+impl Decomposition {
+    /// The transform the parts make up
+    pub fn compose(&self) -> TransformationMatrix {
+        let (t, r, s) = (self.translation, self.rotation, self.scale);
+
+        TransformationMatrix::identity()
+            .scale(s.x, s.y, s.z)
+            .rotate_x(r.x)
+            .rotate_y(r.y)
+            .rotate_z(r.z)
+            .translate(t.x, t.y, t.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+
+    fn assert_matrix_equalish(expected: TransformationMatrix, actual: TransformationMatrix) {
+        for r in 0..4 {
+            for c in 0..4 {
+                assert!(
+                    crate::equalish(expected[[r, c]], actual[[r, c]]),
+                    "Expected {:?}, got {:?}",
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chained_transforms_apply_in_reading_order() {
+        let chained = TransformationMatrix::identity()
+            .rotate_x(PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0);
+
+        assert_eq!(
+            translation(10.0, 5.0, 7.0) * scaling(5.0, 5.0, 5.0) * rotation_x(PI / 2.0),
+            chained
+        );
+    }
+
+    #[test]
+    fn rotation_about_an_axis_matches_the_axis_rotations() {
+        let x = Vector::new(3.0, 0.0, 0.0);
+        let y = Vector::new(0.0, 1.0, 0.0);
+        let z = Vector::new(0.0, 0.0, 0.5);
+
+        assert_matrix_equalish(rotation_x(0.7), rotation(&x, 0.7));
+        assert_matrix_equalish(rotation_y(0.7), rotation(&y, 0.7));
+        assert_matrix_equalish(rotation_z(0.7), rotation(&z, 0.7));
+    }
+
+    #[test]
+    fn decomposing_recovers_the_parts() {
+        let transform = TransformationMatrix::identity()
+            .scale(2.0, 3.0, 4.0)
+            .rotate_x(0.3)
+            .rotate_y(-0.5)
+            .rotate_z(1.2)
+            .translate(1.0, 2.0, 3.0);
+
+        let parts = transform.decompose().unwrap();
+
+        assert!(parts.scale.equalish_to(&Vector::new(2.0, 3.0, 4.0)));
+        assert!(parts.rotation.equalish_to(&Vector::new(0.3, -0.5, 1.2)));
+        assert!(parts.translation.equalish_to(&Vector::new(1.0, 2.0, 3.0)));
+        assert_matrix_equalish(transform, parts.compose());
+    }
+
+    #[test]
+    fn mirroring_decomposes_into_a_negative_scale() {
+        let parts = scaling(-1.0, 2.0, 2.0).decompose().unwrap();
+
+        assert!(parts.scale.equalish_to(&Vector::new(-1.0, 2.0, 2.0)));
+        assert!(parts.rotation.equalish_to(&Vector::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn decomposing_a_quarter_turn_about_y_still_composes_back() {
+        let transform = TransformationMatrix::identity()
+            .rotate_x(0.4)
+            .rotate_y(PI / 2.0)
+            .rotate_z(0.2);
+
+        assert_matrix_equalish(transform, transform.decompose().unwrap().compose());
+    }
+
+    #[test]
+    fn flattening_transforms_cant_be_decomposed() {
+        assert_eq!(None, scaling(1.0, 0.0, 1.0).decompose());
+    }
+}
@@ -0,0 +1,441 @@
+use super::color::Color;
+use super::light::Light;
+use super::math::transforms::{SingularTransform, Transform, TransformationMatrix};
+use super::{Float, Point, Ray, RaytracerWorld, Time, CENTER_ORIGIN, EPSILON};
+
+// Number of points sampled along the part of a ray inside a medium
+const MEDIUM_SAMPLES: usize = 16;
+
+/// Homogeneous fog filling the whole world. Surfaces fade toward `color` with distance and rays
+/// that escape the scene see `color` itself.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fog {
+    pub color: Color,
+    pub density: Float,
+}
+
+impl Fog {
+    pub fn new(color: Color, density: Float) -> Self {
+        Self { color, density }
+    }
+
+    pub fn transmittance(&self, distance: Float) -> Float {
+        (-self.density * distance).exp()
+    }
+
+    pub fn apply(&self, color: Color, distance: Float) -> Color {
+        if distance.is_infinite() {
+            return self.color;
+        }
+
+        let transmittance = self.transmittance(distance);
+
+        color * transmittance + self.color * (1.0 - transmittance)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VolumeShape {
+    /// Unit sphere around the origin
+    Sphere,
+    /// Cube spanning -1 to 1 on every axis
+    Cube,
+}
+
+/// Constant-density participating medium bounded by a transformed unit sphere or cube. Light
+/// passing through is absorbed and scattered once toward the viewer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Medium {
+    pub shape: VolumeShape,
+    transform: Transform,
+    pub density: Float,
+    pub color: Color,
+}
+
+impl Medium {
+    pub fn new(shape: VolumeShape, density: Float, color: Color) -> Self {
+        Self {
+            shape,
+            transform: Transform::identity(),
+            density,
+            color,
+        }
+    }
+
+    pub fn new_sphere(density: Float, color: Color) -> Self {
+        Self::new(VolumeShape::Sphere, density, color)
+    }
+
+    pub fn new_cube(density: Float, color: Color) -> Self {
+        Self::new(VolumeShape::Cube, density, color)
+    }
+
+    pub fn transform(&self) -> TransformationMatrix {
+        self.transform.matrix()
+    }
+
+    /// Replaces the transform, along with the inverse kept for it. Panics if the transform can't
+    /// be inverted; see `try_set_transform`.
+    pub fn set_transform(&mut self, transform: TransformationMatrix) {
+        if let Err(e) = self.try_set_transform(transform) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Replaces the transform, or leaves it alone if the new one can't be inverted
+    pub fn try_set_transform(
+        &mut self,
+        transform: TransformationMatrix,
+    ) -> Result<(), SingularTransform> {
+        self.transform = Transform::new(transform)?;
+
+        Ok(())
+    }
+
+    /// Times at which the ray enters and leaves the medium, clipped to start no earlier than 0
+    pub fn interval(&self, world_ray: &Ray) -> Option<(Time, Time)> {
+        let ray = world_ray.transform(&self.transform.inverse());
+
+        let (t0, t1) = match self.shape {
+            VolumeShape::Sphere => sphere_interval(&ray)?,
+            VolumeShape::Cube => cube_interval(&ray)?,
+        };
+
+        let t0 = t0.max(0.0);
+
+        if t1 <= t0 {
+            None
+        } else {
+            Some((t0, t1))
+        }
+    }
+
+    /// Fraction of light surviving between the ray origin and `max_time`
+    pub fn transmittance(&self, ray: &Ray, max_time: Time) -> Float {
+        match self.interval(ray) {
+            Some((t0, t1)) => {
+                let t1 = t1.min(max_time);
+
+                if t1 <= t0 {
+                    1.0
+                } else {
+                    (-self.density * (t1 - t0) * ray.direction.mag()).exp()
+                }
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Attenuates `color`, seen along `ray` at `max_time`, and adds light scattered toward the
+    /// viewer from inside the medium
+    pub fn apply(&self, world: &RaytracerWorld, ray: &Ray, max_time: Time, color: Color) -> Color {
+        let (t0, t1) = match self.interval(ray) {
+            Some((t0, t1)) => (t0, t1.min(max_time)),
+            None => return color,
+        };
+
+        if t1 <= t0 {
+            return color;
+        }
+
+        let speed = ray.direction.mag();
+        let step = (t1 - t0) / MEDIUM_SAMPLES as Float;
+        let step_transmittance = (-self.density * step * speed).exp();
+
+        let mut transmittance = 1.0;
+        let mut scattered = Color::new(0.0, 0.0, 0.0);
+
+        if let Some(light) = world.light {
+            for i in 0..MEDIUM_SAMPLES {
+                let time = t0 + (i as Float + 0.5) * step;
+                let point = ray.position(time);
+
+                let visibility = world.light_transmittance(point);
+                let arriving = light.intensity * visibility;
+
+                // Light scattered by this slab, dimmed by everything between it and the viewer
+                let scattered_here = self.color * arriving * (1.0 - step_transmittance);
+                scattered = scattered + scattered_here * transmittance;
+
+                transmittance *= step_transmittance;
+            }
+        } else {
+            transmittance = step_transmittance.powi(MEDIUM_SAMPLES as i32);
+        }
+
+        color * transmittance + scattered
+    }
+}
+
+impl RaytracerWorld {
+    /// Fraction of the light reaching `point`: zero behind an opaque object, otherwise whatever
+    /// survives the media and fog along the way
+    pub fn light_transmittance(&self, point: Point) -> Float {
+        if self.is_shadowed(point) {
+            return 0.0;
+        }
+
+        if self.media.is_empty() && self.fog.is_none() {
+            return 1.0;
+        }
+
+        let light: Light = self.light.unwrap();
+        let vector = light.position - point;
+        let distance = vector.mag();
+        let ray = Ray::new(point, vector.norm());
+
+        let through_fog = match self.fog {
+            Some(fog) => fog.transmittance(distance),
+            None => 1.0,
+        };
+
+        self.media
+            .iter()
+            .map(|m| m.transmittance(&ray, distance))
+            .product::<Float>()
+            * through_fog
+    }
+
+    /// Applies the world's media and fog to a color seen along `ray` at `max_time`
+    pub fn attenuate(&self, ray: &Ray, max_time: Time, color: Color) -> Color {
+        let mut color = color;
+
+        if !self.media.is_empty() {
+            // Farthest media first so nearer ones attenuate the light scattered behind them
+            let mut media: Vec<(Time, &Medium)> = self
+                .media
+                .iter()
+                .filter_map(|m| m.interval(ray).map(|(t0, _)| (t0, m)))
+                .filter(|(t0, _)| *t0 < max_time)
+                .collect();
+
+            media.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+            for (_, medium) in media {
+                color = medium.apply(self, ray, max_time, color);
+            }
+        }
+
+        match self.fog {
+            Some(fog) => fog.apply(color, max_time * ray.direction.mag()),
+            None => color,
+        }
+    }
+}
+
+fn sphere_interval(ray: &Ray) -> Option<(Time, Time)> {
+    let sphere_to_ray = ray.origin - CENTER_ORIGIN;
+
+    let a = ray.direction.dot(ray.direction);
+    let b = 2.0 * ray.direction.dot(sphere_to_ray);
+    let c = sphere_to_ray.dot(sphere_to_ray) - 1.0;
+
+    let discriminant = b.powi(2) - 4.0 * a * c;
+
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+    let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+    Some((t0, t1))
+}
+
+fn cube_interval(ray: &Ray) -> Option<(Time, Time)> {
+    let (xmin, xmax) = slab(ray.origin.x(), ray.direction.x());
+    let (ymin, ymax) = slab(ray.origin.y(), ray.direction.y());
+    let (zmin, zmax) = slab(ray.origin.z(), ray.direction.z());
+
+    let t0 = xmin.max(ymin).max(zmin);
+    let t1 = xmax.min(ymax).min(zmax);
+
+    if t0 > t1 {
+        None
+    } else {
+        Some((t0, t1))
+    }
+}
+
+fn slab(origin: Float, direction: Float) -> (Time, Time) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * Float::INFINITY,
+            tmax_numerator * Float::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLACK, WHITE};
+    use crate::math::transforms::{scaling, translation};
+    use crate::Vector;
+
+    #[test]
+    fn fog_fades_surfaces_toward_its_color() {
+        let fog = Fog::new(WHITE, 0.5);
+
+        let near = fog.apply(BLACK, 0.0);
+        let far = fog.apply(BLACK, 100.0);
+        let escaped = fog.apply(BLACK, Float::INFINITY);
+
+        assert!(near.equalish_to(&BLACK), "Actual: {:?}", near);
+        assert!(far.equalish_to(&WHITE), "Actual: {:?}", far);
+        assert!(escaped.equalish_to(&WHITE), "Actual: {:?}", escaped);
+
+        let halfway = fog.apply(BLACK, (2.0 as Float).ln() / 0.5);
+        assert!(
+            halfway.equalish_to(&Color::new(0.5, 0.5, 0.5)),
+            "Actual: {:?}",
+            halfway
+        );
+    }
+
+    #[test]
+    fn a_ray_passes_through_a_cube_medium() {
+        let mut medium = Medium::new_cube(1.0, WHITE);
+        medium.set_transform(scaling(2.0, 2.0, 2.0));
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let (t0, t1) = medium.interval(&ray).unwrap();
+        assert!((t0 - 3.0).abs() < EPSILON && (t1 - 7.0).abs() < EPSILON);
+
+        let expected = (-(4.0 as Float)).exp();
+        assert!((medium.transmittance(&ray, 100.0) - expected).abs() < EPSILON);
+    }
+
+    #[test]
+    fn media_keep_their_transform_if_the_new_one_is_singular() {
+        let mut medium = Medium::new_sphere(1.0, WHITE);
+        medium.set_transform(translation(1.0, 0.0, 0.0));
+
+        assert_eq!(
+            Err(SingularTransform),
+            medium.try_set_transform(scaling(1.0, 0.0, 1.0))
+        );
+        assert_eq!(translation(1.0, 0.0, 0.0), medium.transform());
+    }
+
+    #[test]
+    fn a_ray_starting_inside_a_sphere_medium_is_clipped_to_its_origin() {
+        let medium = Medium::new_sphere(1.0, WHITE);
+        let ray = Ray::new(CENTER_ORIGIN, Vector::new(1.0, 0.0, 0.0));
+
+        assert_eq!(Some((0.0, 1.0)), medium.interval(&ray));
+    }
+
+    #[test]
+    fn a_ray_missing_a_medium_is_unaffected() {
+        let mut medium = Medium::new_sphere(1.0, WHITE);
+        medium.set_transform(translation(5.0, 0.0, 0.0));
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(None, medium.interval(&ray));
+        assert_eq!(1.0, medium.transmittance(&ray, 100.0));
+    }
+
+    #[test]
+    fn world_fog_colors_rays_that_miss() {
+        let world = RaytracerWorld {
+            fog: Some(Fog::new(Color::new(0.5, 0.6, 0.7), 0.1)),
+            ..RaytracerWorld::default()
+        };
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        let c = world.color_at(&ray);
+
+        assert!(c.equalish_to(&Color::new(0.5, 0.6, 0.7)), "Actual: {:?}", c);
+    }
+
+    #[test]
+    fn lit_media_scatter_light_toward_the_viewer() {
+        let mut world = RaytracerWorld::new();
+        world.light = Some(Light::new(Point::new(0.0, 10.0, 0.0), WHITE));
+        world.media.push(Medium::new_sphere(1.0, WHITE));
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = world.color_at(&ray);
+
+        // Two units of medium at density 1 scatter at most 1 - e^-2 of the light, less once the
+        // medium shadows itself
+        let unshadowed = 1.0 - (-(2.0 as Float)).exp();
+        assert!(c.red > 0.1 && c.red < unshadowed, "Actual: {:?}", c);
+    }
+
+    #[test]
+    fn unlit_media_only_absorb() {
+        let mut world = RaytracerWorld::new();
+        world.media.push(Medium::new_sphere(1.0, WHITE));
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = world.attenuate(&ray, Float::INFINITY, WHITE);
+
+        let expected = (-(2.0 as Float)).exp();
+        assert!((c.red - expected).abs() < 0.0001, "Actual: {:?}", c);
+    }
+
+    #[test]
+    fn media_dim_the_light_reaching_a_point() {
+        let mut world = RaytracerWorld::new();
+        world.light = Some(Light::new(Point::new(0.0, 10.0, 0.0), WHITE));
+
+        let mut medium = Medium::new_cube(0.5, WHITE);
+        medium.set_transform(translation(0.0, 5.0, 0.0));
+        world.media.push(medium);
+
+        let visibility = world.light_transmittance(CENTER_ORIGIN);
+
+        assert!((visibility - (-(1.0 as Float)).exp()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn fog_dims_the_light_reaching_a_point() {
+        let mut world = RaytracerWorld::new();
+        world.light = Some(Light::new(Point::new(0.0, 10.0, 0.0), WHITE));
+        world.fog = Some(Fog::new(WHITE, 0.1));
+
+        let visibility = world.light_transmittance(CENTER_ORIGIN);
+
+        assert!((visibility - (-(1.0 as Float)).exp()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn fog_dims_direct_lighting_on_a_lit_surface() {
+        let mut world = RaytracerWorld::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let comps = world.intersect(&ray)[0].prepare_computations(&ray);
+
+        let clear = world.shade_hit(&comps);
+
+        world.fog = Some(Fog::new(BLACK, 0.1));
+        let foggy = world.shade_hit(&comps);
+
+        // Ambient light is all that's left at full strength
+        let ambient = comps.object.material.ambient * comps.object.material.color.red;
+        assert!(
+            foggy.red < clear.red,
+            "Foggy: {:?}, clear: {:?}",
+            foggy,
+            clear
+        );
+        assert!(foggy.red > ambient, "Actual: {:?}", foggy);
+    }
+}
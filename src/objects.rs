@@ -1,9 +1,11 @@
-use std::rc::Rc;
+use super::consts::PI;
+use std::sync::Arc;
 
 use super::material::Material;
 use super::*;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RaytracerObjectType {
     Plane,
     Sphere,
@@ -12,18 +14,59 @@ pub enum RaytracerObjectType {
 
 type ROT = RaytracerObjectType;
 
+/// Box with sides along the axes
 #[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box holding every one of `points`
+    pub fn around(points: &[Point]) -> Self {
+        let mut bounds = Self::new(points[0], points[0]);
+
+        for p in points[1..].iter() {
+            bounds.min = Point::new(
+                bounds.min.x.min(p.x),
+                bounds.min.y.min(p.y),
+                bounds.min.z.min(p.z),
+            );
+            bounds.max = Point::new(
+                bounds.max.x.max(p.x),
+                bounds.max.y.max(p.y),
+                bounds.max.z.max(p.z),
+            );
+        }
+
+        bounds
+    }
+
+    /// The box grown by `amount` on every side
+    pub fn padded(&self, amount: Float) -> Self {
+        let pad = Vector::new(amount, amount, amount);
+
+        Self::new(self.min - pad, self.max + pad)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RaytracerObject {
     obj_id: usize,
     pub obj_type: ROT,
     pub origin: Point,
-    pub transform: TransformationMatrix,
+    transform: Transform,
     pub material: Material,
 }
 
 impl RaytracerObject {
     fn new(obj_id: usize, obj_type: ROT, origin: Point) -> Self {
-        let transform = TransformationMatrix::identity();
+        let transform = Transform::identity();
         let material = Material::new();
 
         Self {
@@ -51,14 +94,70 @@ impl RaytracerObject {
         self.obj_id
     }
 
-    pub fn local_normal_at(&self, _point: Point) -> Vector {
+    pub fn transform(&self) -> TransformationMatrix {
+        self.transform.matrix()
+    }
+
+    /// Replaces the transform, along with the inverse and inverse transpose kept for it. Panics if the
+    /// transform can't be inverted; see `try_set_transform`.
+    pub fn set_transform(&mut self, transform: TransformationMatrix) {
+        if let Err(e) = self.try_set_transform(transform) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Replaces the transform, or leaves it alone if the new one can't be inverted
+    pub fn try_set_transform(
+        &mut self,
+        transform: TransformationMatrix,
+    ) -> Result<(), SingularTransform> {
+        self.transform = Transform::new(transform)?;
+
+        Ok(())
+    }
+
+    /// Takes world space to object space
+    pub fn inverse_transform(&self) -> TransformationMatrix {
+        self.transform.inverse()
+    }
+
+    /// Takes object space normals to world space
+    pub fn inverse_transpose(&self) -> TransformationMatrix {
+        self.transform.inverse_transpose()
+    }
+
+    /// World-space box around the object. None for planes, which go on forever.
+    pub fn bounds(&self) -> Option<Bounds> {
+        match &self.obj_type {
+            ROT::Sphere => {
+                let transform = self.transform.matrix();
+                let mut corners = Vec::with_capacity(8);
+
+                for &x in [-1.0, 1.0].iter() {
+                    for &y in [-1.0, 1.0].iter() {
+                        for &z in [-1.0, 1.0].iter() {
+                            let corner = self.origin + Vector::new(x, y, z);
+
+                            corners.push(transform * corner);
+                        }
+                    }
+                }
+
+                Some(Bounds::around(&corners))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn local_normal_at(&self, point: Point) -> Vector {
         match &self.obj_type {
             ROT::Plane => Vector::new(0.0, 1.0, 0.0),
+            ROT::Sphere => point - CENTER_ORIGIN,
             _ => unimplemented!("Not yet implemented for {:?}", self.obj_type),
         }
     }
 
-    pub fn hit_on_intersect(&self, ray: &Ray) -> Option<Rc<Intersection>> {
+    pub fn hit_on_intersect(&self, ray: &Ray) -> Option<Intersection> {
         match &self.obj_type {
             ROT::Sphere => hit_on_intersect_sphere(self, ray),
             _ => unimplemented!("Not yet implemented for {:?}", self.obj_type),
@@ -68,7 +167,7 @@ impl RaytracerObject {
     pub fn intersect(&self, ray: &Ray) -> Vec<Time> {
         match &self.obj_type {
             ROT::Sphere => self.intersect_sphere(ray),
-            ROT::Plane => self.intersect_plane(ray),
+            ROT::Plane => self.local_intersect(ray),
             _ => unimplemented!("Not yet implemented for {:?}", self.obj_type),
         }
     }
@@ -93,9 +192,7 @@ impl RaytracerObject {
     }
 
     fn intersect_sphere(&self, original_ray: &Ray) -> Vec<Time> {
-        let inverse = self.transform.inverse();
-
-        let ray = original_ray.transform(&inverse);
+        let ray = original_ray.transform(&self.transform.inverse());
 
         let sphere_to_ray = ray.origin - self.origin;
 
@@ -120,30 +217,63 @@ impl RaytracerObject {
         }
     }
 
-    fn intersect_plane(&self, ray: &Ray) -> Vec<Time> {
-        self.local_intersect_plane(ray)
+    pub fn normal_at(&self, world_point: Point) -> Vector {
+        let object_point = self.transform.inverse() * world_point;
+        let object_normal = self.local_normal_at(object_point);
+        let world_normal = &self.transform.inverse_transpose() * object_normal;
+
+        world_normal.norm()
     }
 
-    pub fn normal_at(&self, world_point: Point) -> Vector {
-        // TODO: match on obj_type. Current sphere specific
-        let sphere_transform_inverse = self.transform.inverse();
+    /// Texture coordinates in [0, 1) for a point on the surface
+    pub fn uv_at(&self, world_point: Point) -> (Float, Float) {
+        let p = self.transform.inverse() * world_point;
+
+        match &self.obj_type {
+            ROT::Sphere => {
+                let radius = (p - self.origin).mag();
+                let theta = p.x.atan2(p.z);
+                let phi = (p.y / radius).acos();
 
-        let object_point = &sphere_transform_inverse * world_point;
-        let object_normal = object_point - CENTER_ORIGIN;
-        let world_normal = &sphere_transform_inverse.transposed() * object_normal;
+                let u = 1.0 - (theta / (2.0 * PI) + 0.5);
+                let v = 1.0 - phi / PI;
 
-        world_normal.norm()
+                (u, v)
+            }
+            ROT::Plane => (p.x - p.x.floor(), p.z - p.z.floor()),
+            _ => unimplemented!("Not yet implemented for {:?}", self.obj_type),
+        }
+    }
+
+    /// World-space direction in which `u` increases across the surface
+    pub fn tangent_at(&self, world_point: Point) -> Vector {
+        let p = self.transform.inverse() * world_point;
+
+        let object_tangent = match &self.obj_type {
+            ROT::Sphere => Vector::new(-p.z, 0.0, p.x),
+            ROT::Plane => Vector::new(1.0, 0.0, 0.0),
+            _ => unimplemented!("Not yet implemented for {:?}", self.obj_type),
+        };
+
+        // At the poles every direction is tangent
+        let object_tangent = if object_tangent.mag() < EPSILON {
+            Vector::new(1.0, 0.0, 0.0)
+        } else {
+            object_tangent
+        };
+
+        (&self.transform.matrix() * object_tangent).norm()
     }
 
     pub fn material(&self) -> Material {
-        self.material
+        self.material.clone()
     }
 }
 
-fn hit_on_intersect_sphere(sphere: &RaytracerObject, ray: &Ray) -> Option<Rc<Intersection>> {
+fn hit_on_intersect_sphere(sphere: &RaytracerObject, ray: &Ray) -> Option<Intersection> {
     let mut potential_hit = Intersection {
         time: 0.0,
-        object: Rc::new(sphere.clone()),
+        object: Arc::new(sphere.clone()),
     };
 
     let mut any: bool = false;
@@ -157,7 +287,7 @@ fn hit_on_intersect_sphere(sphere: &RaytracerObject, ray: &Ray) -> Option<Rc<Int
     }
 
     if any {
-        Some(Rc::new(potential_hit))
+        Some(potential_hit)
     } else {
         None
     }
@@ -0,0 +1,498 @@
+//! Camera rays through neighbouring pixels start at the same point and head in nearly the same
+//! direction, so they mostly hit the same things. A `RayPacket` traces four of them together,
+//! one per lane of `math::simd::Float4`, so each object is transformed and tested once for all
+//! four. Boxes around the objects let a packet skip whatever none of its rays come near.
+//!
+//! Only the first hit is found this way. Shading, shadows and media still trace single rays, so
+//! every color comes out exactly as `RaytracerWorld::color_at` would give it.
+
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::math::simd::{Float4, Lanes};
+use crate::math::transforms::TransformationMatrix;
+use crate::objects::{Bounds, RaytracerObject, RaytracerObjectType};
+use crate::{Float, Hittable, Intersection, Ray, RaytracerWorld, Time, EPSILON};
+
+/// Number of rays in a packet
+pub const PACKET_SIZE: usize = 4;
+
+// Boxes only pick which objects to skip, so they're padded by this much of their size
+const BOUNDS_PADDING: Float = 0.001;
+
+// The x, y and z of four points or vectors
+#[derive(Copy, Clone, Debug)]
+struct Xyz {
+    x: Float4,
+    y: Float4,
+    z: Float4,
+}
+
+impl Xyz {
+    fn dot(&self, rhs: &Self) -> Float4 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    // `m * [x, y, z, w]` for each lane, summed in the same order as `math::simd::transform`
+    fn transformed(&self, m: &TransformationMatrix, w: Float) -> Self {
+        let w = Float4::splat(w);
+        let row = |r: usize| {
+            Float4::splat(m[[r, 0]]) * self.x
+                + Float4::splat(m[[r, 1]]) * self.y
+                + Float4::splat(m[[r, 2]]) * self.z
+                + Float4::splat(m[[r, 3]]) * w
+        };
+
+        Self {
+            x: row(0),
+            y: row(1),
+            z: row(2),
+        }
+    }
+}
+
+/// Four rays traced together. The tests against each kind of shape find exactly the times that
+/// `RaytracerObject::intersect` finds for the rays one by one.
+#[derive(Copy, Clone, Debug)]
+pub struct RayPacket {
+    rays: [Ray; PACKET_SIZE],
+    origin: Xyz,
+    direction: Xyz,
+    inverse_direction: Xyz,
+}
+
+impl RayPacket {
+    pub fn new(rays: [Ray; PACKET_SIZE]) -> Self {
+        let lanes =
+            |f: fn(&Ray) -> Float| Float4::new(f(&rays[0]), f(&rays[1]), f(&rays[2]), f(&rays[3]));
+
+        let origin = Xyz {
+            x: lanes(|r| r.origin.x()),
+            y: lanes(|r| r.origin.y()),
+            z: lanes(|r| r.origin.z()),
+        };
+        let direction = Xyz {
+            x: lanes(|r| r.direction.x()),
+            y: lanes(|r| r.direction.y()),
+            z: lanes(|r| r.direction.z()),
+        };
+
+        let one = Float4::splat(1.0);
+        let inverse_direction = Xyz {
+            x: one / direction.x,
+            y: one / direction.y,
+            z: one / direction.z,
+        };
+
+        Self {
+            rays,
+            origin,
+            direction,
+            inverse_direction,
+        }
+    }
+
+    pub fn rays(&self) -> &[Ray; PACKET_SIZE] {
+        &self.rays
+    }
+
+    /// Times each ray enters and leaves `sphere`, nearest first, or None if it misses
+    pub fn intersect_sphere(
+        &self,
+        sphere: &RaytracerObject,
+    ) -> [Option<(Time, Time)>; PACKET_SIZE] {
+        let inverse = sphere.inverse_transform();
+        let origin = self.origin.transformed(&inverse, 1.0);
+        let direction = self.direction.transformed(&inverse, 0.0);
+
+        let sphere_to_ray = Xyz {
+            x: origin.x - Float4::splat(sphere.origin.x()),
+            y: origin.y - Float4::splat(sphere.origin.y()),
+            z: origin.z - Float4::splat(sphere.origin.z()),
+        };
+
+        let a = direction.dot(&direction);
+        let b = Float4::splat(2.0) * direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - Float4::splat(1.0);
+
+        let discriminant = b * b - Float4::splat(4.0) * a * c;
+
+        let negative_b = Float4::splat(-1.0) * b;
+        let root = discriminant.sqrt();
+        let t1 = ((negative_b - root) / (Float4::splat(2.0) * a)).to_array();
+        let t2 = ((negative_b + root) / (Float4::splat(2.0) * a)).to_array();
+
+        let mut times = [None; PACKET_SIZE];
+
+        for (lane, d) in discriminant.to_array().iter().enumerate() {
+            if *d < 0.0 {
+                continue;
+            }
+
+            times[lane] = if t1[lane] < t2[lane] {
+                Some((t1[lane], t2[lane]))
+            } else {
+                Some((t2[lane], t1[lane]))
+            };
+        }
+
+        times
+    }
+
+    /// Time each ray crosses `plane`, or None if it runs along it
+    pub fn intersect_plane(&self, plane: &RaytracerObject) -> [Option<Time>; PACKET_SIZE] {
+        let inverse = plane.inverse_transform();
+        let origin = self.origin.transformed(&inverse, 1.0);
+        let direction = self.direction.transformed(&inverse, 0.0);
+
+        let t = (Float4::splat(-1.0) * origin.y / direction.y).to_array();
+
+        let mut times = [None; PACKET_SIZE];
+
+        for (lane, y) in direction.y.to_array().iter().enumerate() {
+            if y.abs() >= EPSILON {
+                times[lane] = Some(t[lane]);
+            }
+        }
+
+        times
+    }
+
+    /// Whether each ray passes through `bounds` somewhere ahead of its origin
+    pub fn hits_bounds(&self, bounds: &Bounds) -> [bool; PACKET_SIZE] {
+        let slab = |min: Float, max: Float, origin: Float4, inverse_direction: Float4| {
+            let t0 = (Float4::splat(min) - origin) * inverse_direction;
+            let t1 = (Float4::splat(max) - origin) * inverse_direction;
+
+            (t0.min(t1), t0.max(t1))
+        };
+
+        let (x_near, x_far) = slab(
+            bounds.min.x(),
+            bounds.max.x(),
+            self.origin.x,
+            self.inverse_direction.x,
+        );
+        let (y_near, y_far) = slab(
+            bounds.min.y(),
+            bounds.max.y(),
+            self.origin.y,
+            self.inverse_direction.y,
+        );
+        let (z_near, z_far) = slab(
+            bounds.min.z(),
+            bounds.max.z(),
+            self.origin.z,
+            self.inverse_direction.z,
+        );
+
+        let near = x_near.max(y_near).max(z_near).to_array();
+        let far = x_far.min(y_far).min(z_far).to_array();
+
+        let mut hits = [false; PACKET_SIZE];
+
+        for (hit, (near, far)) in hits.iter_mut().zip(near.iter().zip(far.iter())) {
+            // Only a clear miss counts, so a NaN from a ray along the plane of a side is a hit
+            let misses = near > far || *far < 0.0;
+
+            *hit = !misses;
+        }
+
+        hits
+    }
+}
+
+/// Traces rays through a world a packet at a time. Each object's box is worked out when the
+/// tracer is made, so make one per render rather than per row or tile. Worlds with shapes packets
+/// can't be tested against are traced one ray at a time instead.
+pub struct PacketTracer<'a> {
+    world: &'a RaytracerWorld,
+    // One box per object, or None if packets can't be used with this world
+    bounds: Option<Vec<Option<Bounds>>>,
+}
+
+impl<'a> PacketTracer<'a> {
+    pub fn new(world: &'a RaytracerWorld) -> Self {
+        let traceable = world.objects().iter().all(|o| {
+            matches!(
+                o.obj_type,
+                RaytracerObjectType::Sphere | RaytracerObjectType::Plane
+            )
+        });
+
+        let bounds = if traceable {
+            Some(world.objects().iter().map(|o| padded_bounds(o)).collect())
+        } else {
+            None
+        };
+
+        Self { world, bounds }
+    }
+
+    pub fn world(&self) -> &'a RaytracerWorld {
+        self.world
+    }
+
+    /// Whether rays are traced in packets, rather than one at a time
+    pub fn uses_packets(&self) -> bool {
+        self.bounds.is_some()
+    }
+
+    /// The nearest hit ahead of each ray, the same one `RaytracerWorld::intersect` followed by
+    /// `hit` finds
+    pub fn hits(&self, packet: &RayPacket) -> [Option<Intersection>; PACKET_SIZE] {
+        let all_bounds = match &self.bounds {
+            Some(bounds) => bounds,
+            None => {
+                let mut hits: [Option<Intersection>; PACKET_SIZE] = Default::default();
+
+                for (hit, ray) in hits.iter_mut().zip(packet.rays.iter()) {
+                    *hit = self.world.intersect(ray).hit().cloned();
+                }
+
+                return hits;
+            }
+        };
+
+        let objects = self.world.objects();
+        let mut nearest: [Option<(Time, usize)>; PACKET_SIZE] = [None; PACKET_SIZE];
+
+        let mut consider = |lane: usize, time: Time, index: usize| {
+            // Ties go to the earlier object, as they do in the sorted intersections
+            let closer = match nearest[lane] {
+                Some((best, _)) => time < best,
+                None => true,
+            };
+
+            if time >= 0.0 && closer {
+                nearest[lane] = Some((time, index));
+            }
+        };
+
+        for (index, (object, bounds)) in objects.iter().zip(all_bounds.iter()).enumerate() {
+            if let Some(bounds) = bounds {
+                if !packet.hits_bounds(bounds).contains(&true) {
+                    continue;
+                }
+            }
+
+            match object.obj_type {
+                RaytracerObjectType::Sphere => {
+                    for (lane, times) in packet.intersect_sphere(object).iter().enumerate() {
+                        if let Some((t1, t2)) = *times {
+                            consider(lane, t1, index);
+                            consider(lane, t2, index);
+                        }
+                    }
+                }
+                RaytracerObjectType::Plane => {
+                    for (lane, time) in packet.intersect_plane(object).iter().enumerate() {
+                        if let Some(time) = *time {
+                            consider(lane, time, index);
+                        }
+                    }
+                }
+                _ => unreachable!("Packets can't be traced against {:?}", object.obj_type),
+            }
+        }
+
+        let mut hits: [Option<Intersection>; PACKET_SIZE] = Default::default();
+
+        for (hit, nearest) in hits.iter_mut().zip(nearest.iter()) {
+            *hit = nearest.map(|(time, index)| Intersection {
+                time,
+                object: Arc::clone(&objects[index]),
+            });
+        }
+
+        hits
+    }
+
+    /// The color seen along each of `rays`, exactly as `RaytracerWorld::color_at` would give it.
+    /// Rays are traced four at a time, so neighbouring rays should be next to each other, like
+    /// the camera rays for a row of pixels.
+    pub fn colors_at(&self, rays: &[Ray]) -> Vec<Color> {
+        if !self.uses_packets() {
+            return rays.iter().map(|ray| self.world.color_at(ray)).collect();
+        }
+
+        let mut colors = Vec::with_capacity(rays.len());
+
+        for chunk in rays.chunks(PACKET_SIZE) {
+            // A short packet at the end is filled out with copies of its last ray
+            let mut lanes = [chunk[chunk.len() - 1]; PACKET_SIZE];
+            lanes[..chunk.len()].copy_from_slice(chunk);
+
+            let hits = self.hits(&RayPacket::new(lanes));
+
+            for (ray, hit) in chunk.iter().zip(hits.iter()) {
+                colors.push(self.world.color_of_hit(ray, hit.as_ref()));
+            }
+        }
+
+        colors
+    }
+}
+
+// The object's box, grown well past any rounding in the exact tests
+fn padded_bounds(object: &RaytracerObject) -> Option<Bounds> {
+    object.bounds().map(|b| {
+        let size = [b.min.x(), b.min.y(), b.min.z()]
+            .iter()
+            .chain([b.max.x(), b.max.y(), b.max.z()].iter())
+            .fold(1.0 as Float, |size, c| size.max(c.abs()));
+
+        b.padded(size * BOUNDS_PADDING)
+    })
+}
+
+impl RaytracerWorld {
+    /// The color seen along each of `rays`, like `PacketTracer::colors_at`. Renders should make
+    /// one `PacketTracer` and reuse it instead.
+    pub fn colors_at(&self, rays: &[Ray]) -> Vec<Color> {
+        PacketTracer::new(self).colors_at(rays)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::math::transforms::{rotation_x, rotation_z, scaling, translation};
+    use crate::{scenes, Point, Vector, CENTER_ORIGIN};
+
+    fn packet(rays: &[Ray]) -> RayPacket {
+        RayPacket::new([rays[0], rays[1], rays[2], rays[3]])
+    }
+
+    fn rays() -> Vec<Ray> {
+        let origin = Point::new(0.2, 0.3, -5.0);
+
+        vec![
+            Ray::new(origin, Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(origin, Vector::new(0.3, -0.2, 1.0).norm()),
+            Ray::new(origin, Vector::new(0.0, 1.0, 0.0)),
+            Ray::new(
+                Point::new(0.0, 0.5, 0.0),
+                Vector::new(-1.0, 0.0, 0.1).norm(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn sphere_times_match_single_rays() {
+        let mut sphere = RaytracerObject::new_sphere(0, Point::new(0.1, 0.0, 0.0));
+        sphere.set_transform(translation(0.5, 0.0, 1.0) * rotation_z(0.4) * scaling(2.0, 1.0, 1.5));
+
+        let rays = rays();
+        let times = packet(&rays).intersect_sphere(&sphere);
+
+        for (ray, times) in rays.iter().zip(times.iter()) {
+            let expected = sphere.intersect(ray);
+
+            assert_eq!(expected, times.map_or(vec![], |(t1, t2)| vec![t1, t2]));
+        }
+
+        assert_eq!(None, times[2]);
+    }
+
+    #[test]
+    fn plane_times_match_single_rays() {
+        let mut plane = RaytracerObject::new_plane(0, CENTER_ORIGIN);
+        plane.set_transform(translation(0.0, 0.0, 2.0) * rotation_x(PI / 2.0));
+
+        let rays = rays();
+        let times = packet(&rays).intersect_plane(&plane);
+
+        for (ray, time) in rays.iter().zip(times.iter()) {
+            assert_eq!(plane.intersect(ray), time.map_or(vec![], |t| vec![t]));
+        }
+
+        assert_eq!(None, times[2]);
+    }
+
+    #[test]
+    fn packets_hit_the_boxes_they_pass_through() {
+        let bounds = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+
+        let rays = [
+            // Straight through
+            Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            // Passes beside it
+            Ray::new(Point::new(2.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            // Starts inside
+            Ray::new(CENTER_ORIGIN, Vector::new(1.0, 1.0, 0.0).norm()),
+            // Heads away from it
+            Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, -1.0)),
+        ];
+
+        assert_eq!(
+            [true, false, true, false],
+            RayPacket::new(rays).hits_bounds(&bounds)
+        );
+    }
+
+    #[test]
+    fn sphere_bounds_hold_the_transformed_sphere() {
+        let mut sphere = RaytracerObject::new_sphere(0, CENTER_ORIGIN);
+        sphere.set_transform(translation(1.0, 2.0, 3.0) * scaling(2.0, 0.5, 1.0));
+
+        assert_eq!(
+            Some(Bounds::new(
+                Point::new(-1.0, 1.5, 2.0),
+                Point::new(3.0, 2.5, 4.0)
+            )),
+            sphere.bounds()
+        );
+        assert_eq!(None, RaytracerObject::new_plane(1, CENTER_ORIGIN).bounds());
+    }
+
+    #[test]
+    fn hits_match_single_rays() {
+        let scene = scenes::default_world(16.0, 16.0);
+        let rays: Vec<Ray> = (0..16).map(|x| scene.camera.ray_for_pixel(x, 7)).collect();
+        let tracer = PacketTracer::new(&scene.world);
+
+        for four in rays.chunks(PACKET_SIZE) {
+            let hits = tracer.hits(&packet(four));
+
+            for (ray, hit) in four.iter().zip(hits.iter()) {
+                let expected = scene.world.intersect(ray);
+                let expected = expected.hit();
+
+                assert_eq!(expected.map(|h| h.time), hit.as_ref().map(|h| h.time));
+                assert_eq!(
+                    expected.map(|h| h.object.id()),
+                    hit.as_ref().map(|h| h.object.id())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn colors_match_single_rays_in_every_scene() {
+        for name in scenes::SCENE_NAMES.iter() {
+            let scene = scenes::by_name(name, 24.0, 13.0).unwrap();
+
+            // An odd number of rays, so the last packet is only partly used
+            let rays: Vec<Ray> = (0..13)
+                .flat_map(|y| (0..23).map(move |x| (x, y)))
+                .map(|(x, y)| scene.camera.ray_for_pixel(x, y))
+                .collect();
+
+            let expected: Vec<Color> = rays.iter().map(|r| scene.world.color_at(r)).collect();
+
+            assert_eq!(expected, scene.world.colors_at(&rays), "{} differs", name);
+        }
+    }
+
+    #[test]
+    fn worlds_with_other_shapes_trace_single_rays() {
+        let mut world = RaytracerWorld::new();
+        let shape = world.new_test_shape();
+        world.add_object(shape);
+
+        assert!(!PacketTracer::new(&world).uses_packets());
+        assert!(world.colors_at(&[]).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use super::canvas::Canvas;
+use super::color::{Color, BLACK, WHITE};
+use super::{Camera, Float, Hittable, RaytracerWorld};
+
+/// Auxiliary buffers rendered alongside the final image, for compositing.
+///
+/// Pixel values are raw data rather than display colors: `depth` holds the camera-space distance
+/// along the view axis in every channel (infinite where nothing was hit), `normal` holds the
+/// world-space normal's x, y and z in red, green and blue, `object_id` holds the hit object's id
+/// plus one (zero for background) and `albedo` holds the unlit material color. Use the
+/// `*_visualization` methods or `save` for viewable images.
+#[derive(Clone, Debug)]
+pub struct RenderPasses {
+    pub beauty: Canvas,
+    pub depth: Canvas,
+    pub normal: Canvas,
+    pub object_id: Canvas,
+    pub albedo: Canvas,
+}
+
+impl RenderPasses {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            beauty: Canvas::new(width, height),
+            depth: Canvas::of_color(width, height, WHITE * Float::INFINITY),
+            normal: Canvas::new(width, height),
+            object_id: Canvas::new(width, height),
+            albedo: Canvas::new(width, height),
+        }
+    }
+
+    /// Depth remapped so the nearest hit is white, the farthest is black and the background is
+    /// black
+    pub fn depth_visualization(&self) -> Canvas {
+        let depths = self.depth.pixels.iter().map(|p| p.red);
+        let finite = depths.filter(|d| d.is_finite());
+
+        let (near, far) = finite.fold((Float::INFINITY, Float::NEG_INFINITY), |(near, far), d| {
+            (near.min(d), far.max(d))
+        });
+
+        let range = (far - near).max(Float::EPSILON);
+
+        self.map(&self.depth, |p| {
+            if p.red.is_finite() {
+                WHITE * (1.0 - (p.red - near) / range)
+            } else {
+                BLACK
+            }
+        })
+    }
+
+    /// Normals remapped from [-1, 1] to [0, 1] per channel
+    pub fn normal_visualization(&self) -> Canvas {
+        self.map(&self.normal, |p| (p + WHITE) * 0.5)
+    }
+
+    /// A distinct, arbitrary color per object id. Background stays black.
+    pub fn object_id_visualization(&self) -> Canvas {
+        self.map(&self.object_id, |p| {
+            let id = p.red as u32;
+
+            if id == 0 {
+                return BLACK;
+            }
+
+            // Spread consecutive ids across the color wheel
+            let hash = id.wrapping_mul(2_654_435_761);
+
+            Color::new(
+                ((hash >> 16) & 0xff) as Float / 255.0,
+                ((hash >> 8) & 0xff) as Float / 255.0,
+                (hash & 0xff) as Float / 255.0,
+            )
+        })
+    }
+
+    /// Writes each pass as `<name>_<pass>.ppm` in `directory`
+    pub fn save<P: AsRef<Path>>(&self, directory: P, name: &str) -> std::io::Result<()> {
+        let directory = directory.as_ref();
+
+        let passes = [
+            ("beauty", self.beauty.clone()),
+            ("depth", self.depth_visualization()),
+            ("normal", self.normal_visualization()),
+            ("object_id", self.object_id_visualization()),
+            ("albedo", self.albedo.clone()),
+        ];
+
+        for (pass, canvas) in passes.iter() {
+            let path = directory.join(format!("{}_{}.ppm", name, pass));
+
+            let mut file = File::create(path)?;
+            file.write_all(canvas.to_ppm().as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn map<F>(&self, source: &Canvas, f: F) -> Canvas
+    where
+        F: Fn(Color) -> Color,
+    {
+        let mut canvas = source.clone();
+
+        for pixel in canvas.pixels.iter_mut() {
+            *pixel = f(*pixel);
+        }
+
+        canvas
+    }
+}
+
+impl Camera {
+    /// Renders the image together with depth, normal, object id and albedo buffers
+    pub fn render_passes(&self, world: &RaytracerWorld) -> RenderPasses {
+        let mut passes = RenderPasses::new(self.hsize as u32, self.vsize as u32);
+
+        for y in 0..(self.vsize as usize) {
+            for x in 0..(self.hsize as usize) {
+                let ray = self.ray_for_pixel(x, y);
+                let (px, py) = (x as u32, y as u32);
+
+                let intersections = world.intersect(&ray);
+
+                let hit = match intersections.hit() {
+                    Some(hit) => hit,
+                    None => {
+                        let background = world.attenuate(&ray, Float::INFINITY, BLACK);
+                        passes.beauty.write_pixel(px, py, background);
+                        continue;
+                    }
+                };
+
+                let comps = hit.prepare_computations(&ray);
+
+                let color = world.attenuate(&ray, hit.time, world.shade_hit(&comps));
+                passes.beauty.write_pixel(px, py, color);
+
+                // The camera looks down its own negative z axis
+                let camera_point = self.transform() * comps.point;
+                passes.depth.write_pixel(px, py, WHITE * -camera_point.z());
+
+                let n = comps.normalv;
+                passes
+                    .normal
+                    .write_pixel(px, py, Color::new(n.x(), n.y(), n.z()));
+
+                let id = (comps.object.id() + 1) as Float;
+                passes.object_id.write_pixel(px, py, WHITE * id);
+
+                passes
+                    .albedo
+                    .write_pixel(px, py, comps.object.material.color);
+            }
+        }
+
+        passes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::math::transforms::view_transform;
+    use crate::{Point, Vector, CENTER_ORIGIN};
+
+    fn default_camera() -> Camera {
+        let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+        camera.set_transform(view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &CENTER_ORIGIN,
+            &Vector::new(0.0, 1.0, 0.0),
+        ));
+
+        camera
+    }
+
+    #[test]
+    fn passes_at_a_hit() {
+        let world = RaytracerWorld::default();
+        let camera = default_camera();
+
+        let passes = camera.render_passes(&world);
+
+        let beauty = passes.beauty.pixel_at(5, 5);
+        let expected = Color::new(0.38066, 0.47583, 0.2855);
+        assert!(beauty.equalish_to(&expected), "Actual: {:?}", beauty);
+
+        let depth = passes.depth.pixel_at(5, 5).red;
+        assert!((depth - 4.0).abs() < 0.0001, "Actual: {}", depth);
+
+        let normal = passes.normal.pixel_at(5, 5);
+        let expected = Color::new(0.0, 0.0, -1.0);
+        assert!(normal.equalish_to(&expected), "Actual: {:?}", normal);
+
+        let first_id = world.objects()[0].id();
+        assert_eq!((first_id + 1) as Float, passes.object_id.pixel_at(5, 5).red);
+
+        let albedo = passes.albedo.pixel_at(5, 5);
+        assert!(albedo.equalish_to(&Color::new(0.8, 1.0, 0.6)));
+    }
+
+    #[test]
+    fn passes_at_the_background() {
+        let world = RaytracerWorld::default();
+        let camera = default_camera();
+
+        let passes = camera.render_passes(&world);
+
+        assert!(passes.depth.pixel_at(0, 0).red.is_infinite());
+        assert_eq!(BLACK, passes.normal.pixel_at(0, 0));
+        assert_eq!(0.0, passes.object_id.pixel_at(0, 0).red);
+        assert_eq!(BLACK, passes.beauty.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn beauty_pass_matches_a_plain_render() {
+        let world = RaytracerWorld::default();
+        let camera = default_camera();
+
+        let passes = camera.render_passes(&world);
+        let image = camera.render(&world);
+
+        assert_eq!(image.pixels, passes.beauty.pixels);
+    }
+
+    #[test]
+    fn visualizations_fit_the_displayable_range() {
+        let world = RaytracerWorld::default();
+        let camera = default_camera();
+
+        let passes = camera.render_passes(&world);
+
+        assert_eq!(WHITE, passes.depth_visualization().pixel_at(5, 5));
+        assert_eq!(BLACK, passes.depth_visualization().pixel_at(0, 0));
+
+        let normal = passes.normal_visualization().pixel_at(5, 5);
+        assert!(normal.equalish_to(&Color::new(0.5, 0.5, 0.0)));
+
+        assert_ne!(BLACK, passes.object_id_visualization().pixel_at(5, 5));
+        assert_eq!(BLACK, passes.object_id_visualization().pixel_at(0, 0));
+    }
+}
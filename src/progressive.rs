@@ -0,0 +1,308 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::canvas::Canvas;
+use super::color::{Color, BLACK};
+use super::{Camera, Float, RaytracerWorld};
+
+pub const DEFAULT_PREVIEW_BLOCK: u32 = 8;
+pub const DEFAULT_SAMPLES: u32 = 16;
+
+/// Render that refines over several passes: one sample per `preview_block` sized block first,
+/// then one sample per pixel, then further samples per pixel up to `samples`, averaged together.
+/// Each pass yields a snapshot of the whole image.
+#[derive(Clone, Debug)]
+pub struct ProgressiveRender<'a> {
+    pub preview_block: u32,
+    pub samples: u32,
+    world: &'a RaytracerWorld,
+    camera: &'a Camera,
+    previewed: bool,
+    samples_taken: u32,
+    accumulated: Vec<Color>,
+}
+
+impl<'a> ProgressiveRender<'a> {
+    pub fn new(world: &'a RaytracerWorld, camera: &'a Camera) -> Self {
+        let pixels = (camera.hsize as usize) * (camera.vsize as usize);
+
+        Self {
+            preview_block: DEFAULT_PREVIEW_BLOCK,
+            samples: DEFAULT_SAMPLES,
+            world,
+            camera,
+            previewed: false,
+            samples_taken: 0,
+            accumulated: vec![BLACK; pixels],
+        }
+    }
+
+    /// Number of snapshots the render yields in total
+    pub fn passes(&self) -> usize {
+        let preview = if self.preview_block > 1 { 1 } else { 0 };
+
+        preview + self.samples.max(1) as usize
+    }
+
+    /// Runs the render to the end, writing `<name>_<pass>.ppm` to `directory` whenever at least
+    /// `interval` has passed since the last write. The final image is always written.
+    pub fn save_snapshots<P: AsRef<Path>>(
+        self,
+        directory: P,
+        name: &str,
+        interval: Duration,
+    ) -> std::io::Result<Canvas> {
+        let directory = directory.as_ref();
+        let passes = self.passes();
+
+        let mut last_write: Option<Instant> = None;
+        let mut image = None;
+
+        for (pass, snapshot) in self.enumerate() {
+            let due = last_write.map(|t| t.elapsed() >= interval).unwrap_or(true);
+
+            if due || pass + 1 == passes {
+                let path = directory.join(format!("{}_{:03}.ppm", name, pass));
+
+                let mut file = File::create(path)?;
+                file.write_all(snapshot.to_ppm().as_bytes())?;
+
+                last_write = Some(Instant::now());
+            }
+
+            image = Some(snapshot);
+        }
+
+        Ok(image.unwrap_or_else(|| Canvas::new(0, 0)))
+    }
+
+    fn width(&self) -> u32 {
+        self.camera.hsize as u32
+    }
+
+    fn height(&self) -> u32 {
+        self.camera.vsize as u32
+    }
+
+    fn preview(&self) -> Canvas {
+        let (width, height) = (self.width(), self.height());
+        let block = self.preview_block;
+
+        let mut image = Canvas::new(width, height);
+
+        for by in (0..height).step_by(block as usize) {
+            for bx in (0..width).step_by(block as usize) {
+                let x = (bx + block / 2).min(width - 1);
+                let y = (by + block / 2).min(height - 1);
+
+                let ray = self.camera.ray_for_pixel(x as usize, y as usize);
+                let color = self.world.color_at(&ray);
+
+                for py in by..(by + block).min(height) {
+                    for px in bx..(bx + block).min(width) {
+                        image.write_pixel(px, py, color);
+                    }
+                }
+            }
+        }
+
+        image
+    }
+
+    fn add_sample(&mut self) -> Canvas {
+        let (width, height) = (self.width(), self.height());
+        let (dx, dy) = sample_offset(self.samples_taken);
+
+        for y in 0..height {
+            for x in 0..width {
+                let ray = self.camera.ray_for_subpixel(x as usize, y as usize, dx, dy);
+                let i = (x + y * width) as usize;
+
+                self.accumulated[i] = self.accumulated[i] + self.world.color_at(&ray);
+            }
+        }
+
+        self.samples_taken += 1;
+
+        let scale = 1.0 / self.samples_taken as Float;
+
+        Canvas {
+            width,
+            height,
+            pixels: self.accumulated.iter().map(|c| *c * scale).collect(),
+        }
+    }
+}
+
+impl<'a> Iterator for ProgressiveRender<'a> {
+    type Item = Canvas;
+
+    fn next(&mut self) -> Option<Canvas> {
+        if !self.previewed {
+            self.previewed = true;
+
+            if self.preview_block > 1 {
+                return Some(self.preview());
+            }
+        }
+
+        if self.samples_taken < self.samples.max(1) {
+            Some(self.add_sample())
+        } else {
+            None
+        }
+    }
+}
+
+impl Camera {
+    /// Progressive render of `world`, yielding a snapshot per pass
+    pub fn render_progressive<'a>(&'a self, world: &'a RaytracerWorld) -> ProgressiveRender<'a> {
+        ProgressiveRender::new(world, self)
+    }
+}
+
+/// Where in the pixel sample number `sample` goes. The first is the center, like
+/// `ray_for_pixel`; later ones are spread over the pixel by the base 2 and 3 Halton sequences.
+pub fn sample_offset(sample: u32) -> (Float, Float) {
+    if sample == 0 {
+        (0.5, 0.5)
+    } else {
+        (halton(sample, 2), halton(sample, 3))
+    }
+}
+
+fn halton(index: u32, base: u32) -> Float {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    let mut i = index;
+
+    while i > 0 {
+        fraction /= base as Float;
+        result += fraction * (i % base) as Float;
+        i /= base;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::math::transforms::view_transform;
+    use crate::{Point, Vector, CENTER_ORIGIN};
+
+    fn camera(hsize: Float, vsize: Float) -> Camera {
+        let mut camera = Camera::new(hsize, vsize, PI / 2.0);
+        camera.set_transform(view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &CENTER_ORIGIN,
+            &Vector::new(0.0, 1.0, 0.0),
+        ));
+
+        camera
+    }
+
+    #[test]
+    fn halton_sequence_fills_the_unit_interval() {
+        assert_eq!(0.5, halton(1, 2));
+        assert_eq!(0.25, halton(2, 2));
+        assert_eq!(0.75, halton(3, 2));
+        assert!((halton(1, 3) - 1.0 / 3.0).abs() < 1.0e-6);
+        assert!((halton(2, 3) - 2.0 / 3.0).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn preview_comes_first_and_is_blocky() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let mut render = camera.render_progressive(&world);
+        render.preview_block = 4;
+
+        let preview = render.next().unwrap();
+
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(preview.pixel_at(0, 0), preview.pixel_at(x, y));
+            }
+        }
+        assert_eq!(preview.pixel_at(8, 8), preview.pixel_at(10, 10));
+    }
+
+    #[test]
+    fn first_full_pass_matches_a_plain_render() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let mut render = camera.render_progressive(&world);
+        let full = render.nth(1).unwrap();
+
+        assert_eq!(camera.render(&world).pixels, full.pixels);
+    }
+
+    #[test]
+    fn one_snapshot_per_pass() {
+        let world = RaytracerWorld::default();
+        let camera = camera(5.0, 5.0);
+
+        let mut render = camera.render_progressive(&world);
+        render.samples = 3;
+        assert_eq!(4, render.passes());
+        assert_eq!(4, render.count());
+
+        let mut render = camera.render_progressive(&world);
+        render.preview_block = 1;
+        render.samples = 0;
+        assert_eq!(1, render.passes());
+        assert_eq!(1, render.count());
+    }
+
+    #[test]
+    fn extra_samples_soften_the_edges() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let mut render = camera.render_progressive(&world);
+        render.preview_block = 1;
+        render.samples = 8;
+
+        let first = render.next().unwrap();
+        let last = render.last().unwrap();
+
+        assert_eq!(first.pixel_at(5, 5), camera.render(&world).pixel_at(5, 5));
+        assert_ne!(first.pixels, last.pixels);
+    }
+
+    #[test]
+    fn final_snapshot_is_always_saved() {
+        let world = RaytracerWorld::default();
+        let camera = camera(5.0, 5.0);
+        let directory = std::env::temp_dir();
+
+        let mut render = camera.render_progressive(&world);
+        render.samples = 2;
+
+        let name = format!("progressive_{}", std::process::id());
+        let image = render
+            .save_snapshots(&directory, &name, Duration::from_secs(3600))
+            .unwrap();
+
+        let first = directory.join(format!("{}_000.ppm", name));
+        let middle = directory.join(format!("{}_001.ppm", name));
+        let last = directory.join(format!("{}_002.ppm", name));
+
+        assert!(first.exists());
+        assert!(!middle.exists());
+        assert_eq!(
+            image.to_ppm(),
+            std::fs::read_to_string(&last).unwrap(),
+            "Final snapshot should match the returned image"
+        );
+
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(last).unwrap();
+    }
+}
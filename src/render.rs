@@ -0,0 +1,710 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::canvas::Canvas;
+use super::color::{Color, BLACK};
+use super::packet::PacketTracer;
+use super::progressive::sample_offset;
+use super::{Camera, Float, RaytracerWorld};
+
+pub const DEFAULT_TILE_SIZE: u32 = 32;
+
+/// Rectangle of pixels rendered as one unit of work
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The part of the tile that lies inside a `width` by `height` image
+    pub fn clipped(&self, width: u32, height: u32) -> Self {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+
+        Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+
+    // The same pixels with coordinates measured from the corner of `origin`
+    fn relative_to(&self, origin: &Tile) -> Self {
+        Self {
+            x: self.x - origin.x,
+            y: self.y - origin.y,
+            ..*self
+        }
+    }
+}
+
+/// How far along a render is. Units are rows for `Camera::render_with` and tiles for
+/// `TileRenderer::render_with`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RenderProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+}
+
+impl RenderProgress {
+    pub fn fraction(&self) -> Float {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as Float / self.total as Float
+        }
+    }
+
+    /// Estimated time remaining, assuming the rest goes as fast as what's done so far
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+
+        let remaining = self.total.saturating_sub(self.completed) as u32;
+
+        Some(self.elapsed / self.completed as u32 * remaining)
+    }
+}
+
+/// Shared flag for stopping a render early. Clones all refer to the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Optional hooks for long renders. The progress callback may be called from several worker
+/// threads at once.
+#[derive(Clone, Default)]
+pub struct RenderOptions<'a> {
+    pub progress: Option<&'a (dyn Fn(RenderProgress) + Sync)>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl<'a> RenderOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .map(|c| c.is_cancelled())
+            .unwrap_or(false)
+    }
+
+    fn report(&self, completed: usize, total: usize, started: Instant) {
+        if let Some(progress) = self.progress {
+            progress(RenderProgress {
+                completed,
+                total,
+                elapsed: started.elapsed(),
+            });
+        }
+    }
+}
+
+/// Result of a render that may have been cancelled. A cancelled render keeps whatever was
+/// finished, with the rest of the canvas left black.
+#[derive(Clone, Debug)]
+pub enum RenderOutcome {
+    Complete(Canvas),
+    Cancelled(Canvas),
+}
+
+impl RenderOutcome {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, RenderOutcome::Cancelled(_))
+    }
+
+    pub fn canvas(self) -> Canvas {
+        match self {
+            RenderOutcome::Complete(canvas) => canvas,
+            RenderOutcome::Cancelled(canvas) => canvas,
+        }
+    }
+}
+
+/// Multi-threaded renderer. The image is cut into square tiles which idle workers pull from a
+/// shared queue, so a slow corner of the scene can't hold up a whole band of rows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TileRenderer {
+    pub workers: usize,
+    pub tile_size: u32,
+    /// Rays per pixel, averaged. Extra rays are spread over the pixel to smooth edges.
+    pub samples: u32,
+}
+
+impl TileRenderer {
+    pub fn new() -> Self {
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+
+        Self {
+            workers,
+            tile_size: DEFAULT_TILE_SIZE,
+            samples: 1,
+        }
+    }
+
+    /// Tiles covering a `width` by `height` image, left to right and top to bottom. Tiles on the
+    /// right and bottom edges are cropped to fit.
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<Tile> {
+        self.tiles_in(&Tile::new(0, 0, width, height))
+    }
+
+    /// Tiles covering `region`, cropped to fit it
+    pub fn tiles_in(&self, region: &Tile) -> Vec<Tile> {
+        let size = self.tile_size.max(1);
+        let right = region.x + region.width;
+        let bottom = region.y + region.height;
+        let mut tiles = vec![];
+
+        for y in (region.y..bottom).step_by(size as usize) {
+            for x in (region.x..right).step_by(size as usize) {
+                tiles.push(Tile::new(x, y, size.min(right - x), size.min(bottom - y)));
+            }
+        }
+
+        tiles
+    }
+
+    pub fn render(&self, world: &RaytracerWorld, camera: &Camera) -> Canvas {
+        self.render_with(world, camera, &RenderOptions::new())
+            .canvas()
+    }
+
+    /// Renders with progress reported per finished tile. Workers check for cancellation before
+    /// starting each tile.
+    pub fn render_with(
+        &self,
+        world: &RaytracerWorld,
+        camera: &Camera,
+        options: &RenderOptions,
+    ) -> RenderOutcome {
+        let full = Tile::new(0, 0, camera.hsize as u32, camera.vsize as u32);
+        let mut image = Canvas::new(full.width, full.height);
+
+        if self.render_tiles(world, camera, &full, &mut image, options) {
+            RenderOutcome::Complete(image)
+        } else {
+            RenderOutcome::Cancelled(image)
+        }
+    }
+
+    /// Renders only the pixels in `region` of the camera's image into a canvas of the region's
+    /// size. The region is clipped to the image.
+    pub fn render_region(&self, world: &RaytracerWorld, camera: &Camera, region: &Tile) -> Canvas {
+        let region = region.clipped(camera.hsize as u32, camera.vsize as u32);
+        let mut image = Canvas::new(region.width, region.height);
+
+        self.render_tiles(world, camera, &region, &mut image, &RenderOptions::new());
+
+        image
+    }
+
+    /// Renders only the pixels in `region` into the same place in a full-size `image`, leaving
+    /// the rest of it alone
+    pub fn render_region_into(
+        &self,
+        world: &RaytracerWorld,
+        camera: &Camera,
+        region: &Tile,
+        image: &mut Canvas,
+    ) {
+        let region = region
+            .clipped(camera.hsize as u32, camera.vsize as u32)
+            .clipped(image.width, image.height);
+
+        let mut window = Canvas::new(region.width, region.height);
+        self.render_tiles(world, camera, &region, &mut window, &RenderOptions::new());
+
+        image.write_tile(&region, &window.pixels);
+    }
+
+    // Renders the tiles covering `region` into `image`, whose top left corner is the region's.
+    // Returns false if the render was cancelled.
+    fn render_tiles(
+        &self,
+        world: &RaytracerWorld,
+        camera: &Camera,
+        region: &Tile,
+        image: &mut Canvas,
+        options: &RenderOptions,
+    ) -> bool {
+        let tiles = self.tiles_in(region);
+        let next_tile = AtomicUsize::new(0);
+        let completed = AtomicUsize::new(0);
+        let image = Mutex::new(image);
+        let started = Instant::now();
+
+        let workers = self.workers.clamp(1, tiles.len().max(1));
+        let tracer = PacketTracer::new(world);
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    if options.is_cancelled() {
+                        break;
+                    }
+
+                    let index = next_tile.fetch_add(1, Ordering::Relaxed);
+
+                    let tile = match tiles.get(index) {
+                        Some(tile) => *tile,
+                        None => break,
+                    };
+
+                    let colors = camera.trace_tile(&tracer, &tile, self.samples);
+
+                    image
+                        .lock()
+                        .unwrap()
+                        .write_tile(&tile.relative_to(region), &colors);
+
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    options.report(done, tiles.len(), started);
+                });
+            }
+        });
+
+        completed.into_inner() == tiles.len()
+    }
+}
+
+impl Default for TileRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    /// Single-threaded render with progress reported per finished row. Cancellation is checked
+    /// before each row.
+    pub fn render_with(&self, world: &RaytracerWorld, options: &RenderOptions) -> RenderOutcome {
+        let mut image = Canvas::new(self.hsize as u32, self.vsize as u32);
+        let rows = self.vsize as usize;
+        let started = Instant::now();
+        let tracer = PacketTracer::new(world);
+
+        for y in 0..rows {
+            if options.is_cancelled() {
+                return RenderOutcome::Cancelled(image);
+            }
+
+            self.trace_row(&tracer, y, &mut image);
+
+            options.report(y + 1, rows, started);
+        }
+
+        RenderOutcome::Complete(image)
+    }
+
+    /// Renders only the pixels in `region` into a canvas of the region's size. The region is
+    /// clipped to the image.
+    pub fn render_region(&self, world: &RaytracerWorld, region: &Tile) -> Canvas {
+        let region = region.clipped(self.hsize as u32, self.vsize as u32);
+
+        Canvas {
+            width: region.width,
+            height: region.height,
+            pixels: self.render_tile(world, &region),
+        }
+    }
+
+    /// Renders only the pixels in `region` into the same place in a full-size `image`, leaving
+    /// the rest of it alone
+    pub fn render_region_into(&self, world: &RaytracerWorld, region: &Tile, image: &mut Canvas) {
+        let region = region
+            .clipped(self.hsize as u32, self.vsize as u32)
+            .clipped(image.width, image.height);
+
+        image.write_tile(&region, &self.render_tile(world, &region));
+    }
+
+    /// Colors of the pixels in `tile`, row by row. Like `render_tile_sampled`, this is for
+    /// one-off tiles.
+    pub fn render_tile(&self, world: &RaytracerWorld, tile: &Tile) -> Vec<Color> {
+        self.render_tile_sampled(world, tile, 1)
+    }
+
+    /// Like `render_tile`, averaging `samples` rays per pixel. Each call works out every
+    /// object's bounds again, so render whole images with a `TileRenderer`, which does that once
+    /// and shares it across its tiles.
+    pub fn render_tile_sampled(
+        &self,
+        world: &RaytracerWorld,
+        tile: &Tile,
+        samples: u32,
+    ) -> Vec<Color> {
+        self.trace_tile(&PacketTracer::new(world), tile, samples)
+    }
+
+    fn trace_tile(&self, tracer: &PacketTracer, tile: &Tile, samples: u32) -> Vec<Color> {
+        let samples = samples.max(1);
+        let scale = 1.0 / samples as Float;
+        let mut rays = Vec::with_capacity((tile.width * tile.height * samples) as usize);
+
+        for y in tile.y..(tile.y + tile.height) {
+            for x in tile.x..(tile.x + tile.width) {
+                for sample in 0..samples {
+                    let (dx, dy) = sample_offset(sample);
+
+                    rays.push(self.ray_for_subpixel(x as usize, y as usize, dx, dy));
+                }
+            }
+        }
+
+        tracer
+            .colors_at(&rays)
+            .chunks(samples as usize)
+            .map(|pixel| pixel.iter().fold(BLACK, |sum, &color| sum + color) * scale)
+            .collect()
+    }
+}
+
+impl Canvas {
+    /// Copies the row-by-row `colors` of a tile into place
+    pub fn write_tile(&mut self, tile: &Tile, colors: &[Color]) {
+        let rows = colors.chunks(tile.width as usize);
+
+        for (y, row) in (tile.y..).zip(rows) {
+            let start = (tile.x + y * self.width) as usize;
+
+            self.pixels[start..start + row.len()].copy_from_slice(row);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::math::transforms::view_transform;
+    use crate::{Point, Vector, CENTER_ORIGIN};
+
+    fn camera(hsize: Float, vsize: Float) -> Camera {
+        let mut camera = Camera::new(hsize, vsize, PI / 2.0);
+        camera.set_transform(view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &CENTER_ORIGIN,
+            &Vector::new(0.0, 1.0, 0.0),
+        ));
+
+        camera
+    }
+
+    #[test]
+    fn tiles_cover_every_pixel_exactly_once() {
+        let renderer = TileRenderer {
+            workers: 1,
+            tile_size: 8,
+            samples: 1,
+        };
+
+        let tiles = renderer.tiles(21, 10);
+
+        assert_eq!(6, tiles.len());
+        assert_eq!(
+            Tile {
+                x: 16,
+                y: 8,
+                width: 5,
+                height: 2
+            },
+            tiles[5]
+        );
+
+        let covered: u32 = tiles.iter().map(|t| t.width * t.height).sum();
+        assert_eq!(21 * 10, covered);
+    }
+
+    #[test]
+    fn default_renderer_uses_every_core() {
+        let renderer = TileRenderer::default();
+
+        assert!(renderer.workers >= 1);
+        assert_eq!(DEFAULT_TILE_SIZE, renderer.tile_size);
+    }
+
+    #[test]
+    fn tiled_render_matches_single_threaded_render() {
+        let world = RaytracerWorld::default();
+        let camera = camera(23.0, 17.0);
+
+        let renderer = TileRenderer {
+            workers: 3,
+            tile_size: 5,
+            samples: 1,
+        };
+
+        let expected = camera.render(&world);
+        let actual = renderer.render(&world, &camera);
+
+        assert_eq!(expected.pixels, actual.pixels);
+    }
+
+    #[test]
+    fn progress_estimates_the_time_remaining() {
+        let progress = RenderProgress {
+            completed: 1,
+            total: 4,
+            elapsed: Duration::from_secs(2),
+        };
+
+        assert_eq!(0.25, progress.fraction());
+        assert_eq!(Some(Duration::from_secs(6)), progress.eta());
+
+        let started = RenderProgress {
+            completed: 0,
+            ..progress
+        };
+        assert_eq!(None, started.eta());
+    }
+
+    #[test]
+    fn progress_is_reported_for_every_tile() {
+        let world = RaytracerWorld::default();
+        let camera = camera(10.0, 10.0);
+
+        let renderer = TileRenderer {
+            workers: 2,
+            tile_size: 5,
+            samples: 1,
+        };
+
+        let reports = Mutex::new(vec![]);
+        let record = |p: RenderProgress| reports.lock().unwrap().push(p.completed);
+
+        let options = RenderOptions {
+            progress: Some(&record),
+            ..RenderOptions::new()
+        };
+
+        let outcome = renderer.render_with(&world, &camera, &options);
+        assert!(!outcome.is_cancelled());
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_unstable();
+        assert_eq!(vec![1, 2, 3, 4], reports);
+    }
+
+    #[test]
+    fn cancelling_a_tiled_render_keeps_the_finished_tiles() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let renderer = TileRenderer {
+            workers: 1,
+            tile_size: 6,
+            samples: 1,
+        };
+
+        let token = CancellationToken::new();
+        let stop = |_: RenderProgress| token.cancel();
+
+        let options = RenderOptions {
+            progress: Some(&stop),
+            cancellation: Some(token.clone()),
+        };
+
+        let outcome = renderer.render_with(&world, &camera, &options);
+        assert!(outcome.is_cancelled());
+
+        let expected = camera.render(&world);
+        let partial = outcome.canvas();
+
+        // Only the top left tile was rendered
+        assert_eq!(expected.pixel_at(5, 5), partial.pixel_at(5, 5));
+        assert_ne!(expected.pixel_at(6, 5), partial.pixel_at(6, 5));
+        assert_eq!(BLACK, partial.pixel_at(6, 5));
+    }
+
+    #[test]
+    fn cancelling_a_single_threaded_render_stops_after_the_current_row() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let token = CancellationToken::new();
+        let stop_at_middle = |p: RenderProgress| {
+            if p.completed == 6 {
+                token.cancel();
+            }
+        };
+
+        let options = RenderOptions {
+            progress: Some(&stop_at_middle),
+            cancellation: Some(token.clone()),
+        };
+
+        let outcome = camera.render_with(&world, &options);
+        assert!(outcome.is_cancelled());
+
+        let partial = outcome.canvas();
+        let expected = Color::new(0.38066, 0.47583, 0.2855);
+        assert!(partial.pixel_at(5, 5).equalish_to(&expected));
+        assert_eq!(BLACK, partial.pixel_at(5, 6));
+    }
+
+    #[test]
+    fn a_render_cancelled_up_front_is_blank() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let options = RenderOptions {
+            cancellation: Some(token),
+            ..RenderOptions::new()
+        };
+
+        let outcome = TileRenderer::default().render_with(&world, &camera, &options);
+
+        assert!(outcome.is_cancelled());
+        assert!(outcome.canvas().pixels.iter().all(|p| *p == BLACK));
+    }
+
+    #[test]
+    fn tiles_cover_a_region() {
+        let renderer = TileRenderer {
+            workers: 1,
+            tile_size: 4,
+            samples: 1,
+        };
+
+        let tiles = renderer.tiles_in(&Tile::new(3, 2, 6, 5));
+
+        assert_eq!(4, tiles.len());
+        assert_eq!(Tile::new(3, 2, 4, 4), tiles[0]);
+        assert_eq!(Tile::new(7, 6, 2, 1), tiles[3]);
+    }
+
+    #[test]
+    fn regions_are_clipped_to_the_image() {
+        let region = Tile::new(8, 3, 10, 4).clipped(10, 5);
+
+        assert_eq!(Tile::new(8, 3, 2, 2), region);
+        assert_eq!(0, Tile::new(12, 0, 5, 5).clipped(10, 5).width);
+    }
+
+    #[test]
+    fn region_render_matches_the_same_pixels_of_a_full_render() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+        let full = camera.render(&world);
+
+        let region = Tile::new(3, 4, 5, 3);
+
+        let renderer = TileRenderer {
+            workers: 2,
+            tile_size: 2,
+            samples: 1,
+        };
+
+        let single = camera.render_region(&world, &region);
+        let threaded = renderer.render_region(&world, &camera, &region);
+
+        assert_eq!((5, 3), (single.width, single.height));
+        assert_eq!(single.pixels, threaded.pixels);
+
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(full.pixel_at(x + 3, y + 4), single.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn region_render_into_a_full_canvas_leaves_the_rest_alone() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+        let full = camera.render(&world);
+
+        let region = Tile::new(4, 4, 3, 3);
+        let marker = Color::new(1.0, 0.0, 1.0);
+
+        let mut single = Canvas::of_color(11, 11, marker);
+        camera.render_region_into(&world, &region, &mut single);
+
+        let mut threaded = Canvas::of_color(11, 11, marker);
+        TileRenderer::default().render_region_into(&world, &camera, &region, &mut threaded);
+
+        assert_eq!(single.pixels, threaded.pixels);
+        assert_eq!(full.pixel_at(5, 5), single.pixel_at(5, 5));
+        assert_eq!(full.pixel_at(6, 6), single.pixel_at(6, 6));
+        assert_eq!(marker, single.pixel_at(3, 5));
+        assert_eq!(marker, single.pixel_at(7, 5));
+    }
+
+    #[test]
+    fn samples_are_averaged_and_match_progressive_rendering() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let renderer = TileRenderer {
+            samples: 4,
+            ..TileRenderer::new()
+        };
+
+        let mut progressive = camera.render_progressive(&world);
+        progressive.preview_block = 1;
+        progressive.samples = 4;
+        let expected = progressive.last().unwrap();
+
+        let actual = renderer.render(&world, &camera);
+
+        for (e, a) in expected.pixels.iter().zip(actual.pixels.iter()) {
+            assert!(e.equalish_to(a), "Expected {:?} but got {:?}", e, a);
+        }
+        assert_ne!(camera.render(&world).pixels, actual.pixels);
+    }
+
+    #[test]
+    fn zero_workers_still_renders() {
+        let world = RaytracerWorld::default();
+        let camera = camera(11.0, 11.0);
+
+        let renderer = TileRenderer {
+            workers: 0,
+            tile_size: 4,
+            samples: 1,
+        };
+
+        let image = renderer.render(&world, &camera);
+
+        let expected = Color::new(0.38066, 0.47583, 0.2855);
+        assert!(image.pixel_at(5, 5).equalish_to(&expected));
+    }
+}
@@ -0,0 +1,252 @@
+use super::consts::PI;
+use std::fmt;
+use std::io;
+
+use yaml_rust::scanner::ScanError;
+
+use super::bump::NormalPerturbation;
+use super::color::{Color, WHITE};
+use super::light::Light;
+use super::material::Material;
+use super::math::transforms::*;
+use super::media::{Fog, Medium};
+use super::{Camera, Float, Point, RaytracerWorld, Vector, CENTER_ORIGIN};
+
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod yaml;
+
+/// Names accepted by `by_name`
+pub const SCENE_NAMES: [&str; 3] = ["default", "spheres", "atmosphere"];
+
+/// A world together with the camera it's meant to be seen through
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scene {
+    pub world: RaytracerWorld,
+    pub camera: Camera,
+}
+
+impl Scene {
+    pub fn new(world: RaytracerWorld, camera: Camera) -> Self {
+        Self { world, camera }
+    }
+}
+
+/// Why a scene file couldn't be loaded
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// The file isn't valid YAML
+    Syntax(ScanError),
+    /// The YAML doesn't describe a scene. `line` counts from 1.
+    Invalid {
+        line: usize,
+        message: String,
+    },
+    /// The file isn't valid JSON or doesn't describe a scene
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+}
+
+impl SceneError {
+    /// Line of the file the error was found on, if it came from the file's contents
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            SceneError::Io(_) => None,
+            SceneError::Syntax(e) => Some(e.marker().line()),
+            SceneError::Invalid { line, .. } => Some(*line),
+            #[cfg(feature = "serde")]
+            SceneError::Json(e) => Some(e.line()),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "could not read scene: {}", e),
+            SceneError::Syntax(e) => write!(f, "invalid YAML: {}", e),
+            SceneError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+            #[cfg(feature = "serde")]
+            SceneError::Json(e) => write!(f, "invalid scene: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<ScanError> for SceneError {
+    fn from(e: ScanError) -> Self {
+        SceneError::Syntax(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for SceneError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneError::Json(e)
+    }
+}
+
+/// One of the built-in scenes, rendered at `width` by `height` pixels
+pub fn by_name(name: &str, width: Float, height: Float) -> Option<Scene> {
+    match name {
+        "default" => Some(default_world(width, height)),
+        "spheres" => Some(spheres(width, height, PI / 3.0)),
+        "atmosphere" => Some(atmosphere(width, height)),
+        _ => None,
+    }
+}
+
+/// The book's default world: two nested spheres and a single light
+pub fn default_world(width: Float, height: Float) -> Scene {
+    let mut camera = Camera::new(width, height, PI / 2.0);
+    camera.set_transform(view_transform(
+        &Point::new(0.0, 0.0, -5.0),
+        &CENTER_ORIGIN,
+        &Vector::new(0.0, 1.0, 0.0),
+    ));
+
+    Scene::new(RaytracerWorld::default(), camera)
+}
+
+/// Index of the large sphere in the middle of `spheres` among the world's objects, for
+/// animating it
+pub const SPHERES_MIDDLE: usize = 3;
+
+/// Three spheres in a room made of flattened spheres, as in the sphere example
+pub fn spheres(width: Float, height: Float, field_of_view: Float) -> Scene {
+    let mut world = RaytracerWorld::new();
+    world.light = Some(Light::new(Point::new(-10.0, 10.0, -10.0), WHITE));
+
+    let mut floor = world.new_sphere(CENTER_ORIGIN);
+    floor.set_transform(scaling(10.0, 0.01, 10.0));
+    floor.material = Material::default();
+    floor.material.color = Color::new(1.0, 0.9, 0.9);
+    floor.material.specular = 0.0;
+
+    let floor_material = floor.material.clone();
+
+    world.add_object(floor);
+
+    let mut left_wall = world.new_sphere(CENTER_ORIGIN);
+    left_wall.set_transform(
+        translation(0.0, 0.0, 5.0)
+            * rotation_y(-PI / 4.0)
+            * rotation_x(PI / 2.0)
+            * scaling(10.0, 0.01, 10.0),
+    );
+    left_wall.material = floor_material.clone();
+    world.add_object(left_wall);
+
+    let mut right_wall = world.new_sphere(CENTER_ORIGIN);
+    right_wall.set_transform(
+        translation(0.0, 0.0, 5.0)
+            * rotation_y(PI / 4.0)
+            * rotation_x(PI / 2.0)
+            * scaling(10.0, 0.01, 10.0),
+    );
+    right_wall.material = floor_material;
+    world.add_object(right_wall);
+
+    let mut middle = world.new_sphere(CENTER_ORIGIN);
+    middle.set_transform(translation(-0.5, 1.0, 0.5));
+    middle.material.color = Color::new(0.1, 1.0, 0.5);
+    middle.material.diffuse = 0.7;
+    middle.material.specular = 0.3;
+    assert_eq!(SPHERES_MIDDLE, world.objects().len());
+    world.add_object(middle);
+
+    let mut right = world.new_sphere(CENTER_ORIGIN);
+    right.set_transform(translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5));
+    right.material.color = Color::new(0.5, 1.0, 0.1);
+    right.material.diffuse = 0.7;
+    right.material.specular = 0.3;
+    world.add_object(right);
+
+    let mut left = world.new_sphere(CENTER_ORIGIN);
+    left.set_transform(translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33));
+    left.material.color = Color::new(1.0, 0.8, 0.1);
+    left.material.diffuse = 0.7;
+    left.material.specular = 0.3;
+    world.add_object(left);
+
+    let mut camera = Camera::new(width, height, field_of_view);
+    camera.set_transform(view_transform(
+        &Point::new(0.0, 1.5, -5.0),
+        &Point::new(0.0, 1.0, 0.0),
+        &Vector::new(0.0, 1.0, 0.0),
+    ));
+
+    Scene::new(world, camera)
+}
+
+/// Bumpy spheres over rippled water, seen through fog and a lit cloud. Exercises normal
+/// perturbation and participating media.
+pub fn atmosphere(width: Float, height: Float) -> Scene {
+    let mut world = RaytracerWorld::new();
+    world.light = Some(Light::new(Point::new(-5.0, 8.0, -6.0), WHITE));
+    world.fog = Some(Fog::new(Color::new(0.6, 0.65, 0.75), 0.04));
+
+    let mut water = world.new_plane();
+    water.material.color = Color::new(0.1, 0.3, 0.5);
+    water.material.specular = 0.8;
+    water.material.shininess = 150.0;
+    water.material.normal_perturbation = NormalPerturbation::Ripples {
+        frequency: 6.0,
+        amplitude: 0.15,
+        turbulence: 0.5,
+    };
+    world.add_object(water);
+
+    let mut stone = world.new_sphere(CENTER_ORIGIN);
+    stone.set_transform(translation(-0.8, 1.0, 0.5));
+    stone.material.color = Color::new(0.7, 0.6, 0.5);
+    stone.material.normal_perturbation = NormalPerturbation::Noise {
+        scale: 4.0,
+        amplitude: 0.3,
+    };
+    world.add_object(stone);
+
+    let mut pebble = world.new_sphere(CENTER_ORIGIN);
+    pebble.set_transform(translation(1.2, 0.5, -0.6) * scaling(0.5, 0.5, 0.5));
+    pebble.material.color = Color::new(0.9, 0.3, 0.2);
+    world.add_object(pebble);
+
+    let mut cloud = Medium::new_sphere(0.6, Color::new(0.9, 0.9, 1.0));
+    cloud.set_transform(translation(0.5, 2.3, 1.5) * scaling(1.5, 0.6, 1.0));
+    world.media.push(cloud);
+
+    let mut camera = Camera::new(width, height, PI / 3.0);
+    camera.set_transform(view_transform(
+        &Point::new(0.0, 1.8, -5.0),
+        &Point::new(0.0, 1.0, 0.0),
+        &Vector::new(0.0, 1.0, 0.0),
+    ));
+
+    Scene::new(world, camera)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_named_scene_exists() {
+        for name in SCENE_NAMES.iter() {
+            let scene = by_name(name, 20.0, 10.0).unwrap();
+
+            assert_eq!(20.0, scene.camera.hsize);
+            assert!(!scene.world.objects().is_empty());
+        }
+
+        assert!(by_name("nope", 20.0, 10.0).is_none());
+    }
+}
@@ -0,0 +1,170 @@
+//! Scenes saved as JSON, for tools that generate them. Everything in the world is written out,
+//! including object ids, and floats are written with enough digits to read back exactly, so a
+//! saved scene loads as an identical copy. Infinite and NaN values can't be represented in JSON.
+//! Cameras are just their size, field of view and transform, so tools don't have to work out the
+//! pixel size themselves.
+
+use std::fs;
+use std::path::Path;
+
+use super::{Scene, SceneError};
+
+/// Reads the JSON scene file at `path`
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Writes `scene` to `path` as JSON
+pub fn save<P: AsRef<Path>>(scene: &Scene, path: P) -> Result<(), SceneError> {
+    fs::write(path, to_string(scene)?)?;
+
+    Ok(())
+}
+
+/// Builds the scene described by `source`
+pub fn parse(source: &str) -> Result<Scene, SceneError> {
+    Ok(serde_json::from_str(source)?)
+}
+
+/// `scene` as indented JSON
+pub fn to_string(scene: &Scene) -> Result<String, SceneError> {
+    Ok(serde_json::to_string_pretty(scene)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bump::NormalPerturbation;
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::math::transforms::scaling;
+    use crate::scenes;
+    use crate::Camera;
+    use std::sync::Arc;
+
+    #[test]
+    fn scenes_round_trip_exactly() {
+        for name in scenes::SCENE_NAMES.iter() {
+            let scene = scenes::by_name(name, 16.0, 8.0).unwrap();
+
+            let saved = to_string(&scene).unwrap();
+            let loaded = parse(&saved).unwrap();
+
+            assert_eq!(saved, to_string(&loaded).unwrap(), "{} changed", name);
+            assert_eq!(
+                scene.camera.render(&scene.world).pixels,
+                loaded.camera.render(&loaded.world).pixels,
+                "{} renders differently",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn normal_maps_are_saved_with_the_material() {
+        let mut scene = scenes::default_world(4.0, 4.0);
+
+        let mut map = Canvas::of_color(2, 1, Color::new(0.5, 0.5, 1.0));
+        map.write_pixel(1, 0, Color::new(0.1, 0.2, 0.3));
+
+        let sphere = scene.world.get_object_mut(0);
+        sphere.material.normal_perturbation = NormalPerturbation::NormalMap(Arc::new(map));
+
+        let loaded = parse(&to_string(&scene).unwrap()).unwrap();
+
+        match &loaded.world.objects()[0].material.normal_perturbation {
+            NormalPerturbation::NormalMap(map) => {
+                assert_eq!(Color::new(0.1, 0.2, 0.3), map.pixel_at(1, 0))
+            }
+            other => panic!("Expected a normal map, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn saved_files_load() {
+        let scene = scenes::atmosphere(8.0, 4.0);
+        let path = std::env::temp_dir().join(format!("scene_{}.json", std::process::id()));
+
+        save(&scene, &path).unwrap();
+        let loaded = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(scene.world.fog, loaded.world.fog);
+        assert_eq!(scene.world.media, loaded.world.media);
+        assert_eq!(scene.camera.transform(), loaded.camera.transform());
+    }
+
+    #[test]
+    fn singular_transforms_are_rejected() {
+        let mut scene = scenes::default_world(4.0, 4.0);
+        scene
+            .world
+            .get_object_mut(0)
+            .set_transform(scaling(7.0, 1.0, 1.0));
+
+        let flattened = to_string(&scene).unwrap().replacen("7.0", "0.0", 1);
+        let error = parse(&flattened).unwrap_err();
+
+        assert!(matches!(error, SceneError::Json(_)));
+        assert!(error.to_string().contains("can't be inverted"));
+    }
+
+    #[test]
+    fn cameras_are_saved_without_derived_fields() {
+        let scene = scenes::default_world(4.0, 2.0);
+        let saved = serde_json::to_value(&scene.camera).unwrap();
+
+        let mut keys: Vec<&String> = saved.as_object().unwrap().keys().collect();
+        keys.sort();
+
+        assert_eq!(vec!["field_of_view", "hsize", "transform", "vsize"], keys);
+    }
+
+    #[test]
+    fn hand_written_cameras_load() {
+        let camera: Camera = serde_json::from_str(
+            "{
+  \"hsize\": 200,
+  \"vsize\": 125,
+  \"field_of_view\": 1.5707964,
+  \"transform\": {
+    \"values\": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]
+  }
+}",
+        )
+        .unwrap();
+
+        let expected = Camera::new(200.0, 125.0, 1.5707964);
+
+        assert_eq!(expected.pixel_size, camera.pixel_size);
+        assert_eq!(expected.half_width, camera.half_width);
+        assert_eq!(expected.half_height, camera.half_height);
+    }
+
+    #[test]
+    fn cameras_that_cant_render_are_rejected() {
+        let camera = |hsize: &str, field_of_view: &str| {
+            serde_json::from_str::<Camera>(&format!(
+                "{{\"hsize\": {}, \"vsize\": 10, \"field_of_view\": {}, \"transform\": \
+                 {{\"values\": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]}}}}",
+                hsize, field_of_view
+            ))
+        };
+
+        assert!(camera("10", "1").is_ok());
+
+        let error = camera("0", "1").unwrap_err();
+        assert!(error.to_string().contains("hsize and vsize"), "{}", error);
+
+        let error = camera("10", "4").unwrap_err();
+        assert!(error.to_string().contains("field_of_view"), "{}", error);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = parse("{\n  \"world\": {},\n  \"camera\": 3\n}").unwrap_err();
+
+        assert!(matches!(error, SceneError::Json(_)));
+        assert_eq!(Some(2), error.line());
+    }
+}
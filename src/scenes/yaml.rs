@@ -0,0 +1,911 @@
+//! Scene files in the YAML format used by the Ray Tracer Challenge's bonus scenes:
+//!
+//! ```yaml
+//! - add: camera
+//!   width: 100
+//!   height: 50
+//!   field-of-view: 0.785
+//!   from: [0, 1.5, -5]
+//!   to: [0, 1, 0]
+//!   up: [0, 1, 0]
+//!
+//! - add: light
+//!   at: [-10, 10, -10]
+//!   intensity: [1, 1, 1]
+//!
+//! - define: shiny
+//!   value:
+//!     specular: 0.9
+//!     shininess: 300
+//!
+//! - define: red-shiny
+//!   extend: shiny
+//!   value:
+//!     color: [1, 0, 0]
+//!
+//! - add: sphere
+//!   material: red-shiny
+//!   transform:
+//!     - [scale, 0.5, 0.5, 0.5]
+//!     - [translate, 0, 0.5, 0]
+//! ```
+//!
+//! Transforms are applied in the order listed. Spheres and planes are the only shapes, and
+//! materials take `color`, `ambient`, `diffuse`, `specular` and `shininess`. The book's other
+//! shapes, its reflection, refraction and pattern properties and `shadow` are skipped with a
+//! warning, so its scene files still load with what the renderer can do.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+use super::{Scene, SceneError};
+use crate::color::Color;
+use crate::light::Light;
+use crate::material::Material;
+use crate::math::transforms::*;
+use crate::{Camera, Float, Point, RaytracerWorld, Vector, CENTER_ORIGIN};
+
+type Result<T> = std::result::Result<T, SceneError>;
+
+// Shapes and material properties from the book that the renderer can't do yet
+const SKIPPED_SHAPES: [&str; 4] = ["cube", "cylinder", "cone", "group"];
+const SKIPPED_PROPERTIES: [&str; 3] = ["reflective", "transparency", "refractive-index"];
+
+/// Something in a scene file that was skipped because the renderer can't do it yet
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    /// Counts from 1, like `SceneError::Invalid`
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn invalid<T>(node: &Node, message: String) -> Result<T> {
+    Err(SceneError::Invalid {
+        line: node.line,
+        message,
+    })
+}
+
+/// Reads the scene file at `path`, ignoring anything skipped
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene> {
+    load_with_warnings(path).map(|(scene, _)| scene)
+}
+
+/// Reads the scene file at `path`, along with warnings for anything skipped
+pub fn load_with_warnings<P: AsRef<Path>>(path: P) -> Result<(Scene, Vec<Warning>)> {
+    parse_with_warnings(&fs::read_to_string(path)?)
+}
+
+/// Builds the scene described by `source`, ignoring anything skipped
+pub fn parse(source: &str) -> Result<Scene> {
+    parse_with_warnings(source).map(|(scene, _)| scene)
+}
+
+/// Builds the scene described by `source`, along with warnings for anything skipped
+pub fn parse_with_warnings(source: &str) -> Result<(Scene, Vec<Warning>)> {
+    let root = match read_tree(source)? {
+        Some(root) => root,
+        None => {
+            return Err(SceneError::Invalid {
+                line: 1,
+                message: "the file is empty".to_string(),
+            })
+        }
+    };
+
+    SceneBuilder::new().build(&root)
+}
+
+// YAML nodes remembering the line they started on, which yaml-rust's own `Yaml` doesn't
+#[derive(Clone, Debug)]
+struct Node {
+    line: usize,
+    value: Value,
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Scalar(String),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+}
+
+impl Node {
+    fn describe(&self) -> &'static str {
+        match self.value {
+            Value::Scalar(_) => "a value",
+            Value::Sequence(_) => "a list",
+            Value::Mapping(_) => "a mapping",
+        }
+    }
+
+    fn as_str(&self) -> Result<&str> {
+        match &self.value {
+            Value::Scalar(s) => Ok(s),
+            _ => invalid(
+                self,
+                format!("expected a name but found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_sequence(&self) -> Result<&[Node]> {
+        match &self.value {
+            Value::Sequence(items) => Ok(items),
+            _ => invalid(
+                self,
+                format!("expected a list but found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_mapping(&self) -> Result<&[(Node, Node)]> {
+        match &self.value {
+            Value::Mapping(entries) => Ok(entries),
+            _ => invalid(
+                self,
+                format!("expected a mapping but found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_number(&self) -> Result<Float> {
+        match &self.value {
+            Value::Scalar(s) => s
+                .parse()
+                .or_else(|_| invalid(self, format!("expected a number but found {:?}", s))),
+            _ => invalid(
+                self,
+                format!("expected a number but found {}", self.describe()),
+            ),
+        }
+    }
+
+    fn as_triple(&self) -> Result<(Float, Float, Float)> {
+        let items = self.as_sequence()?;
+
+        if items.len() != 3 {
+            return invalid(
+                self,
+                format!("expected 3 numbers but found {}", items.len()),
+            );
+        }
+
+        Ok((
+            items[0].as_number()?,
+            items[1].as_number()?,
+            items[2].as_number()?,
+        ))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<&Node>> {
+        for (k, v) in self.as_mapping()? {
+            if k.as_str()? == key {
+                return Ok(Some(v));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn require(&self, key: &str) -> Result<&Node> {
+        match self.get(key)? {
+            Some(value) => Ok(value),
+            None => invalid(self, format!("missing {:?}", key)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TreeBuilder {
+    // Collections being filled in, with their anchor and any mapping key waiting for a value
+    open: Vec<(Node, usize, Option<Node>)>,
+    anchors: HashMap<usize, Node>,
+    root: Option<Node>,
+}
+
+impl TreeBuilder {
+    fn finish(&mut self, node: Node, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+
+        let (parent, _, key) = match self.open.last_mut() {
+            Some(open) => open,
+            None => {
+                self.root = Some(node);
+                return;
+            }
+        };
+
+        match &mut parent.value {
+            Value::Sequence(items) => items.push(node),
+            Value::Mapping(entries) => match key.take() {
+                Some(k) => entries.push((k, node)),
+                None => *key = Some(node),
+            },
+            Value::Scalar(_) => unreachable!("scalars are never left open"),
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some((node, anchor, _)) = self.open.pop() {
+            self.finish(node, anchor);
+        }
+    }
+}
+
+impl MarkedEventReceiver for TreeBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let line = mark.line();
+
+        match event {
+            Event::Scalar(value, _, anchor, _) => {
+                let node = Node {
+                    line,
+                    value: Value::Scalar(value),
+                };
+                self.finish(node, anchor);
+            }
+            Event::SequenceStart(anchor) => {
+                let node = Node {
+                    line,
+                    value: Value::Sequence(vec![]),
+                };
+                self.open.push((node, anchor, None));
+            }
+            Event::MappingStart(anchor) => {
+                let node = Node {
+                    line,
+                    value: Value::Mapping(vec![]),
+                };
+                self.open.push((node, anchor, None));
+            }
+            Event::SequenceEnd | Event::MappingEnd => self.close(),
+            Event::Alias(anchor) => {
+                // The parser rejects unknown aliases, so the anchor is always there
+                if let Some(node) = self.anchors.get(&anchor).cloned() {
+                    self.finish(node, 0);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_tree(source: &str) -> Result<Option<Node>> {
+    let mut builder = TreeBuilder::default();
+    Parser::new(source.chars()).load(&mut builder, false)?;
+
+    Ok(builder.root)
+}
+
+struct SceneBuilder {
+    world: RaytracerWorld,
+    camera: Option<Camera>,
+    defines: HashMap<String, Node>,
+    warnings: Vec<Warning>,
+}
+
+impl SceneBuilder {
+    fn new() -> Self {
+        Self {
+            world: RaytracerWorld::new(),
+            camera: None,
+            defines: HashMap::new(),
+            warnings: vec![],
+        }
+    }
+
+    // Once per line, since a defined material shared by many shapes is only read from one place
+    fn warn(&mut self, node: &Node, message: String) {
+        let warning = Warning {
+            line: node.line,
+            message,
+        };
+
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    fn build(mut self, root: &Node) -> Result<(Scene, Vec<Warning>)> {
+        for item in root.as_sequence()? {
+            if let Some(kind) = item.get("add")? {
+                self.add(kind.as_str()?, item)?;
+            } else if let Some(name) = item.get("define")? {
+                self.define(name.as_str()?, item)?;
+            } else {
+                return invalid(item, "expected \"add\" or \"define\"".to_string());
+            }
+        }
+
+        match self.camera {
+            Some(camera) => Ok((Scene::new(self.world, camera), self.warnings)),
+            None => invalid(root, "the scene has no camera".to_string()),
+        }
+    }
+
+    fn add(&mut self, kind: &str, item: &Node) -> Result<()> {
+        match kind {
+            "camera" => self.add_camera(item),
+            "light" => self.add_light(item),
+            "sphere" | "plane" => self.add_shape(kind, item),
+            _ if SKIPPED_SHAPES.contains(&kind) => {
+                self.warn(
+                    item,
+                    format!("skipped {:?}, which isn't supported yet", kind),
+                );
+                Ok(())
+            }
+            _ => invalid(item, format!("unknown item {:?}", kind)),
+        }
+    }
+
+    fn add_camera(&mut self, item: &Node) -> Result<()> {
+        check_keys(
+            item,
+            &[
+                "add",
+                "width",
+                "height",
+                "field-of-view",
+                "from",
+                "to",
+                "up",
+            ],
+        )?;
+
+        let mut camera = Camera::new(
+            item.require("width")?.as_number()?,
+            item.require("height")?.as_number()?,
+            item.require("field-of-view")?.as_number()?,
+        );
+
+        let (x, y, z) = item.require("from")?.as_triple()?;
+        let from = Point::new(x, y, z);
+        let (x, y, z) = item.require("to")?.as_triple()?;
+        let to = Point::new(x, y, z);
+        let (x, y, z) = item.require("up")?.as_triple()?;
+        let up = Vector::new(x, y, z);
+
+        if camera
+            .try_set_transform(view_transform(&from, &to, &up))
+            .is_err()
+        {
+            return invalid(item, "camera needs different from and to points, and an up vector off the line between them".to_string());
+        }
+
+        self.camera = Some(camera);
+
+        Ok(())
+    }
+
+    fn add_light(&mut self, item: &Node) -> Result<()> {
+        check_keys(item, &["add", "at", "intensity"])?;
+
+        if self.world.light.is_some() {
+            return invalid(item, "only one light is supported".to_string());
+        }
+
+        let (x, y, z) = item.require("at")?.as_triple()?;
+        let (r, g, b) = item.require("intensity")?.as_triple()?;
+
+        self.world.light = Some(Light::new(Point::new(x, y, z), Color::new(r, g, b)));
+
+        Ok(())
+    }
+
+    fn add_shape(&mut self, kind: &str, item: &Node) -> Result<()> {
+        check_keys(item, &["add", "material", "transform", "shadow"])?;
+
+        if let Some(shadow) = item.get("shadow")? {
+            match shadow.as_str()? {
+                "true" | "false" => self.warn(
+                    shadow,
+                    "skipped \"shadow\", which isn't supported yet".to_string(),
+                ),
+                other => {
+                    return invalid(
+                        shadow,
+                        format!("expected true or false but found {:?}", other),
+                    )
+                }
+            }
+        }
+
+        let mut shape = match kind {
+            "plane" => self.world.new_plane(),
+            _ => self.world.new_sphere(CENTER_ORIGIN),
+        };
+
+        if let Some(material) = item.get("material")? {
+            shape.material = self.material(material)?;
+        }
+
+        if let Some(transform) = item.get("transform")? {
+            if shape.try_set_transform(self.transform(transform)?).is_err() {
+                return invalid(
+                    transform,
+                    "transform can't be inverted (is something scaled by zero?)".to_string(),
+                );
+            }
+        }
+
+        self.world.add_object(shape);
+
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, item: &Node) -> Result<()> {
+        check_keys(item, &["define", "extend", "value"])?;
+
+        let mut value = item.require("value")?.clone();
+
+        if let Some(base) = item.get("extend")? {
+            let base = self.lookup(base)?;
+
+            value.value = match (&base.value, value.value) {
+                (Value::Mapping(inherited), Value::Mapping(own)) => {
+                    Value::Mapping(inherited.iter().cloned().chain(own).collect())
+                }
+                (Value::Sequence(inherited), Value::Sequence(own)) => {
+                    Value::Sequence(inherited.iter().cloned().chain(own).collect())
+                }
+                _ => return invalid(item, format!("{:?} can't extend {}", name, base.describe())),
+            };
+        }
+
+        self.defines.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    fn lookup(&self, name: &Node) -> Result<&Node> {
+        let key = name.as_str()?;
+
+        match self.defines.get(key) {
+            Some(value) => Ok(value),
+            None => invalid(name, format!("{:?} hasn't been defined", key)),
+        }
+    }
+
+    fn material(&mut self, node: &Node) -> Result<Material> {
+        let node = match node.value {
+            Value::Scalar(_) => self.lookup(node)?.clone(),
+            _ => node.clone(),
+        };
+
+        let mut material = Material::new();
+
+        // Later entries win, so an extended definition overrides what it inherits
+        for (key, value) in node.as_mapping()? {
+            match key.as_str()? {
+                "color" => {
+                    let (r, g, b) = value.as_triple()?;
+                    material.color = Color::new(r, g, b);
+                }
+                "ambient" => material.ambient = value.as_number()?,
+                "diffuse" => material.diffuse = value.as_number()?,
+                "specular" => material.specular = value.as_number()?,
+                "shininess" => material.shininess = value.as_number()?,
+                "pattern" => {
+                    value.as_mapping()?;
+                    self.warn(
+                        key,
+                        "skipped \"pattern\", which isn't supported yet".to_string(),
+                    );
+                }
+                other if SKIPPED_PROPERTIES.contains(&other) => {
+                    value.as_number()?;
+                    self.warn(
+                        key,
+                        format!("skipped {:?}, which isn't supported yet", other),
+                    );
+                }
+                other => return invalid(key, format!("unsupported material property {:?}", other)),
+            }
+        }
+
+        Ok(material)
+    }
+
+    fn transform(&self, node: &Node) -> Result<TransformationMatrix> {
+        let mut transform = TransformationMatrix::identity();
+
+        for step in node.as_sequence()? {
+            let matrix = match step.value {
+                Value::Scalar(_) => self.transform(self.lookup(step)?)?,
+                _ => transformation(step)?,
+            };
+
+            transform = matrix * transform;
+        }
+
+        Ok(transform)
+    }
+}
+
+// A single `[operation, arguments...]` transform step
+fn transformation(step: &Node) -> Result<TransformationMatrix> {
+    let items = step.as_sequence()?;
+
+    let (operation, arguments) = match items.split_first() {
+        Some((operation, arguments)) => (operation.as_str()?, arguments),
+        None => return invalid(step, "empty transform".to_string()),
+    };
+
+    let expected = match operation {
+        "translate" | "scale" => 3,
+        "rotate-x" | "rotate-y" | "rotate-z" => 1,
+        "shear" => 6,
+        _ => return invalid(step, format!("unknown transform {:?}", operation)),
+    };
+
+    if arguments.len() != expected {
+        return invalid(
+            step,
+            format!(
+                "{} takes {} numbers but found {}",
+                operation,
+                expected,
+                arguments.len()
+            ),
+        );
+    }
+
+    let a = arguments
+        .iter()
+        .map(Node::as_number)
+        .collect::<Result<Vec<Float>>>()?;
+
+    Ok(match operation {
+        "translate" => translation(a[0], a[1], a[2]),
+        "scale" => scaling(a[0], a[1], a[2]),
+        "rotate-x" => rotation_x(a[0]),
+        "rotate-y" => rotation_y(a[0]),
+        "rotate-z" => rotation_z(a[0]),
+        _ => shearing(a[0], a[1], a[2], a[3], a[4], a[5]),
+    })
+}
+
+fn check_keys(item: &Node, allowed: &[&str]) -> Result<()> {
+    for (key, _) in item.as_mapping()? {
+        let name = key.as_str()?;
+
+        if !allowed.contains(&name) {
+            return invalid(key, format!("unexpected key {:?}", name));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::PI;
+    use crate::{equalish, Ray};
+
+    const CAMERA: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+";
+
+    fn scene(items: &str) -> Result<Scene> {
+        parse(&format!("{}{}", CAMERA, items))
+    }
+
+    fn error_line(items: &str) -> usize {
+        scene(items).unwrap_err().line().unwrap()
+    }
+
+    #[test]
+    fn camera_and_light() {
+        let scene = scene(
+            "
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 0.5, 1]
+",
+        )
+        .unwrap();
+
+        assert_eq!(100.0, scene.camera.hsize);
+        assert_eq!(50.0, scene.camera.vsize);
+        assert_eq!(0.785, scene.camera.field_of_view);
+        assert_eq!(
+            view_transform(
+                &Point::new(0.0, 1.5, -5.0),
+                &Point::new(0.0, 1.0, 0.0),
+                &Vector::new(0.0, 1.0, 0.0)
+            ),
+            scene.camera.transform()
+        );
+        assert_eq!(
+            Some(Light::new(
+                Point::new(-10.0, 10.0, -10.0),
+                Color::new(1.0, 0.5, 1.0)
+            )),
+            scene.world.light
+        );
+    }
+
+    #[test]
+    fn transforms_apply_in_order() {
+        let scene = scene(
+            "
+- add: sphere
+  transform:
+    - [scale, 2, 2, 2]
+    - [rotate-y, 1.5707963267948966]
+    - [translate, 1, 0, 0]
+- add: plane
+",
+        )
+        .unwrap();
+
+        let objects = scene.world.objects();
+        assert_eq!(2, objects.len());
+        assert_eq!(
+            translation(1.0, 0.0, 0.0) * rotation_y(PI / 2.0) * scaling(2.0, 2.0, 2.0),
+            objects[0].transform()
+        );
+        assert_eq!(TransformationMatrix::identity(), objects[1].transform());
+    }
+
+    #[test]
+    fn planes_are_hit_where_their_transform_puts_them() {
+        let scene = scene(
+            "
+- add: plane
+  transform:
+    - [translate, 0, 2, 0]
+- add: plane
+  transform:
+    - [rotate-x, 1.5707963267948966]
+    - [translate, 0, 0, 3]
+",
+        )
+        .unwrap();
+
+        let down = Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let ahead = Ray::new(Point::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0));
+
+        let floor = &scene.world.objects()[0];
+        let wall = &scene.world.objects()[1];
+
+        assert_eq!(vec![3.0], floor.intersect(&down));
+        assert_eq!(1, wall.intersect(&ahead).len());
+        assert!(equalish(4.0, wall.intersect(&ahead)[0]));
+        assert!(wall.intersect(&down).is_empty());
+    }
+
+    #[test]
+    fn materials_extend_definitions() {
+        let scene = scene(
+            "
+- define: base
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    specular: 0.2
+- define: blue
+  extend: base
+  value:
+    color: [0, 0, 1]
+- add: sphere
+  material: blue
+- add: sphere
+  material:
+    shininess: 10
+",
+        )
+        .unwrap();
+
+        let objects = scene.world.objects();
+
+        let blue = &objects[0].material;
+        assert_eq!(Color::new(0.0, 0.0, 1.0), blue.color);
+        assert_eq!(0.7, blue.diffuse);
+        assert_eq!(0.2, blue.specular);
+        assert_eq!(Material::new().ambient, blue.ambient);
+
+        assert_eq!(10.0, objects[1].material.shininess);
+    }
+
+    #[test]
+    fn transforms_can_be_defined_and_nested() {
+        let scene = scene(
+            "
+- define: small
+  value:
+    - [scale, 0.5, 0.5, 0.5]
+- define: small-and-raised
+  value:
+    - small
+    - [translate, 0, 1, 0]
+- add: sphere
+  transform:
+    - small-and-raised
+    - [translate, 2, 0, 0]
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            translation(2.0, 0.0, 0.0) * translation(0.0, 1.0, 0.0) * scaling(0.5, 0.5, 0.5),
+            scene.world.objects()[0].transform()
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        // The camera takes up lines 1 to 8
+        assert_eq!(11, error_line("\n- add: sphere\n  material: missing\n"));
+        assert_eq!(
+            11,
+            error_line("- add: sphere\n  transform:\n    - [scale, 1, 2]\n")
+        );
+        assert_eq!(
+            11,
+            error_line("- add: sphere\n  material:\n    glow: 0.5\n")
+        );
+        assert_eq!(
+            11,
+            error_line("- add: sphere\n  material:\n    reflective: high\n")
+        );
+        assert_eq!(10, error_line("- add: sphere\n  shadow: maybe\n"));
+        assert_eq!(9, error_line("- add: teapot\n"));
+        assert_eq!(
+            10,
+            error_line("- add: light\n  at: [0, 0]\n  intensity: [1, 1, 1]\n")
+        );
+    }
+
+    #[test]
+    fn book_features_are_skipped_with_warnings() {
+        let (scene, warnings) = parse_with_warnings(&format!(
+            "{}{}",
+            CAMERA,
+            "
+- define: glass
+  value:
+    color: [0.1, 0.1, 0.1]
+    reflective: 0.9
+    transparency: 0.9
+    refractive-index: 1.5
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [1, 1, 1]
+        - [0, 0, 0]
+- add: sphere
+  material: glass
+  shadow: false
+- add: sphere
+  material: glass
+- add: cube
+  transform:
+    - [scale, 2, 2, 2]
+- add: cylinder
+  min: 0
+  max: 1
+  closed: true
+- add: group
+  children:
+    - add: cone
+"
+        ))
+        .unwrap();
+
+        // The plane and both spheres, with everything they can use
+        let objects = scene.world.objects();
+        assert_eq!(3, objects.len());
+        assert_eq!(Color::new(0.1, 0.1, 0.1), objects[1].material.color);
+
+        // In the order they're found, once each even though two spheres use the glass
+        let lines: Vec<usize> = warnings.iter().map(|w| w.line).collect();
+        assert_eq!(vec![18, 25, 13, 14, 15, 28, 31, 35], lines);
+        assert_eq!(
+            "line 25: skipped \"shadow\", which isn't supported yet",
+            warnings[1].to_string()
+        );
+        assert_eq!(
+            "line 28: skipped \"cube\", which isn't supported yet",
+            warnings[5].to_string()
+        );
+    }
+
+    #[test]
+    fn transforms_must_be_invertible() {
+        let error = scene("- add: sphere\n  transform:\n    - [scale, 0, 1, 1]\n").unwrap_err();
+
+        assert_eq!(Some(11), error.line());
+        assert!(error.to_string().contains("can't be inverted"));
+
+        let error = parse(
+            "- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 0, 1]
+",
+        )
+        .unwrap_err();
+
+        assert_eq!(Some(1), error.line());
+    }
+
+    #[test]
+    fn invalid_yaml_is_a_syntax_error() {
+        let error = parse("- add: camera\n  width: [1, 2\n").unwrap_err();
+
+        assert!(matches!(error, SceneError::Syntax(_)));
+        assert!(error.line().is_some());
+    }
+
+    #[test]
+    fn a_scene_needs_a_camera() {
+        let error = parse("- add: plane\n").unwrap_err();
+
+        assert_eq!("line 1: the scene has no camera", error.to_string());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn example_file_matches_the_built_in_scene() {
+        let file = parse(include_str!("../../examples/spheres.yml")).unwrap();
+        let built_in = super::super::spheres(400.0, 200.0, PI / 3.0);
+
+        let thumbnail = |scene: &Scene| {
+            let mut camera = Camera::new(40.0, 20.0, scene.camera.field_of_view);
+            camera.set_transform(scene.camera.transform());
+
+            camera.render(&scene.world)
+        };
+
+        let diff = thumbnail(&file).diff(&thumbnail(&built_in), 0.01);
+
+        assert_eq!(built_in.camera.hsize, file.camera.hsize);
+        assert_eq!(6, file.world.objects().len());
+        assert_eq!(0, diff.pixels_over_threshold);
+    }
+
+    #[test]
+    fn anchors_are_followed() {
+        let scene = scene(
+            "
+- add: sphere
+  material: &red
+    color: [1, 0, 0]
+- add: sphere
+  material: *red
+",
+        )
+        .unwrap();
+
+        let objects = scene.world.objects();
+        assert_eq!(objects[0].material, objects[1].material);
+    }
+}
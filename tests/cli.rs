@@ -0,0 +1,133 @@
+//! Runs the command line renderer and checks its exit codes: 2 for bad arguments, 1 for failures
+//! while loading or writing, 0 otherwise.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use ray_tracer_challenge::canvas::Canvas;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ray_tracer_challenge"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn output_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+fn assert_exit_code(expected: i32, output: &Output) {
+    assert_eq!(
+        Some(expected),
+        output.status.code(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn an_unknown_scene_is_a_usage_error() {
+    let output = run(&["no-such-scene", "-q", "-o", "unused.ppm"]);
+
+    assert_exit_code(2, &output);
+}
+
+#[test]
+fn an_unknown_output_extension_is_a_usage_error() {
+    let path = output_path("unknown.bmp");
+    let output = run(&["default", "-q", "-o", path.to_str().unwrap()]);
+
+    assert_exit_code(2, &output);
+    assert!(!path.exists());
+}
+
+#[test]
+fn an_unknown_scene_file_extension_is_a_usage_error() {
+    let scene = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let output = run(&[scene, "-q", "-o", "unused.ppm"]);
+
+    assert_exit_code(2, &output);
+}
+
+#[test]
+fn a_bad_field_of_view_is_a_usage_error() {
+    let path = output_path("bad_fov.ppm");
+
+    for fov in ["NaN", "inf", "0", "-1", "4"].iter() {
+        let output = run(&[
+            "default",
+            "-q",
+            "--width",
+            "4",
+            "--height",
+            "2",
+            "--fov",
+            fov,
+            "-o",
+            path.to_str().unwrap(),
+        ]);
+
+        assert_exit_code(2, &output);
+    }
+
+    assert!(!path.exists());
+}
+
+#[test]
+fn an_unwritable_output_path_is_a_failure() {
+    let path = output_path("no-such-directory").join("image.ppm");
+    let output = run(&["default", "-q", "-o", path.to_str().unwrap()]);
+
+    assert_exit_code(1, &output);
+}
+
+#[test]
+fn a_tiny_render_writes_an_image() {
+    let path = output_path("tiny.ppm");
+    let _ = fs::remove_file(&path);
+
+    let output = run(&[
+        "default",
+        "-q",
+        "--width",
+        "8",
+        "--height",
+        "4",
+        "-o",
+        path.to_str().unwrap(),
+    ]);
+
+    assert_exit_code(0, &output);
+
+    let canvas = Canvas::from_ppm(fs::read(&path).unwrap()).unwrap();
+    assert_eq!((8, 4), (canvas.width, canvas.height));
+}
+
+#[test]
+fn unsupported_yaml_is_skipped_with_a_warning() {
+    let scene = output_path("book.yml");
+    fs::write(
+        &scene,
+        "- add: camera
+  width: 8
+  height: 4
+  field-of-view: 1
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: cube
+",
+    )
+    .unwrap();
+
+    let path = output_path("book.ppm");
+    let output = run(&[scene.to_str().unwrap(), "-o", path.to_str().unwrap()]);
+
+    assert_exit_code(0, &output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 11: skipped \"cube\""));
+}
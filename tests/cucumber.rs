@@ -3,6 +3,7 @@
 #![recursion_limit = "256"]
 
 use std::rc::Rc;
+use std::sync::Arc;
 
 use cucumber::{after, before, cucumber};
 
@@ -38,7 +39,7 @@ pub struct MyWorld {
     direction: Vector,
     n: Vector,
     inv: TransformationMatrix,
-    tuple: (f32, f32, f32, f32),
+    tuple: (Float, Float, Float, Float),
     r: Ray,
     rv: Vector,
     r2: Ray,
@@ -47,11 +48,11 @@ pub struct MyWorld {
     s2: RaytracerObject,
     plane: RaytracerObject,
     xs: Intersections,
-    i: Option<Rc<Intersection>>,
-    i1: Option<Rc<Intersection>>,
-    i2: Option<Rc<Intersection>>,
-    i3: Option<Rc<Intersection>>,
-    i4: Option<Rc<Intersection>>,
+    i: Option<Intersection>,
+    i1: Option<Intersection>,
+    i2: Option<Intersection>,
+    i3: Option<Intersection>,
+    i4: Option<Intersection>,
     intensity: Color,
     position: Point,
     light: Light,
@@ -59,21 +60,22 @@ pub struct MyWorld {
     eyev: Vector,
     normalv: Vector,
     result: Color,
-    shape: Rc<RaytracerObject>,
+    shape: Arc<RaytracerObject>,
     comps: Option<PrecomputedHit>,
     c: Color,
     from: Point,
     to: Point,
     up: Vector,
-    hsize: f32,
-    vsize: f32,
-    field_of_view: f32,
+    hsize: Float,
+    vsize: Float,
+    field_of_view: Float,
     camera: Camera,
     image: Canvas,
     in_shadow: bool,
     n1: Vector,
     n2: Vector,
     n3: Vector,
+    transform_result: Result<(), SingularTransform>,
 }
 
 impl cucumber::World for MyWorld {}
@@ -84,7 +86,7 @@ impl std::default::Default for MyWorld {
         let s1 = rw.new_sphere(CENTER_ORIGIN);
         let s2 = rw.new_sphere(CENTER_ORIGIN);
         let plane = rw.new_test_shape();
-        let shape = Rc::new(rw.new_sphere(CENTER_ORIGIN));
+        let shape = Arc::new(rw.new_sphere(CENTER_ORIGIN));
 
         // This function is called every time a new scenario is started
         MyWorld {
@@ -146,13 +148,15 @@ impl std::default::Default for MyWorld {
             n1: STATIONARY,
             n2: STATIONARY,
             n3: STATIONARY,
+            transform_result: Ok(()),
         }
     }
 }
 
 mod example_steps {
-    use std::f32::consts::PI;
+    use ray_tracer_challenge::consts::PI;
     use std::rc::Rc;
+    use std::sync::Arc;
 
     use regex::Regex;
 
@@ -372,7 +376,7 @@ mod example_steps {
             let r: usize = matches[1].parse().unwrap();
             let c: usize = matches[2].parse().unwrap();
 
-            let expected: f32 = matches[3].parse().unwrap();
+            let expected: Float = matches[3].parse().unwrap();
 
             let actual = match world.matrix {
                 AnyMatrix::M2x2(m) => m[[r, c]],
@@ -387,9 +391,9 @@ mod example_steps {
         given regex r"^c(.*) = color\((.*), (.*), (.*)\)$" |world, matches, _step| {
             let color_i: usize = matches[1].parse().unwrap();
 
-            let red: f32 = matches[2].parse().unwrap();
-            let green: f32 = matches[3].parse().unwrap();
-            let blue: f32 = matches[4].parse().unwrap();
+            let red: Float = matches[2].parse().unwrap();
+            let green: Float = matches[3].parse().unwrap();
+            let blue: Float = matches[4].parse().unwrap();
 
             let color = Color::new(red, green, blue);
 
@@ -397,10 +401,10 @@ mod example_steps {
         };
 
         given regex r"^b ← tuple\((.*), (.*), (.*), (.*)\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
-            let t4: f32 = matches[4].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
+            let t4: Float = matches[4].parse().unwrap();
 
             world.tuple = (t1, t2, t3, t4);
         };
@@ -416,37 +420,37 @@ mod example_steps {
         };
 
         given "B ← inverse(A)" |world, _step| {
-            world.matrix_b = Rc::new(AnyMatrix::M4x4(m4x4(&world.matrix_a).inverse()));
+            world.matrix_b = Rc::new(AnyMatrix::M4x4(m4x4(&world.matrix_a).inverse().unwrap()));
         };
 
         given regex r"^transform ← translation\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
 
             world.transform = Rc::new(translation(t1, t2, t3));
         };
 
         given regex r"^m ← translation\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
 
             world.m = Rc::new(translation(t1, t2, t3).into());
         };
 
         given regex r"^m ← scaling\(([0-9.]*), ([0-9.]*), ([0-9.]*)\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
 
             world.m = Rc::new(scaling(t1, t2, t3).into());
         };
 
         given regex r"^m ← scaling\(([0-9.]*), ([0-9.]*), ([0-9.]*)\) \* rotation_z\(π/5\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
 
             let st = scaling(t1, t2, t3);
             let rzt = rotation_z(PI / 5.0);
@@ -455,138 +459,138 @@ mod example_steps {
         };
 
         given regex r"^transform ← scaling\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
 
             world.transform = Rc::new(scaling(t1, t2, t3));
         };
 
         given regex r"^p ← point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.p = Point::new(x, y, z);
         };
 
         given regex r"^v ← vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.v = Vector::new(x, y, z);
         };
 
         given "inv ← inverse(transform)" |world, _step| {
-            world.inv = world.transform.inverse();
+            world.inv = world.transform.inverse().unwrap();
         };
 
         given regex r"^half_quarter ← rotation_x\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.half_quarter = Rc::new(rotation_x(PI / denominator));
         };
 
         given regex r"^half_quarter ← rotation_y\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.half_quarter = Rc::new(rotation_y(PI / denominator));
         };
 
         given regex r"^half_quarter ← rotation_z\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.half_quarter = Rc::new(rotation_z(PI / denominator));
         };
 
         given regex r"^full_quarter ← rotation_x\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.full_quarter = Rc::new(rotation_x(PI / denominator));
         };
 
         given regex r"^full_quarter ← rotation_y\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.full_quarter = Rc::new(rotation_y(PI / denominator));
         };
 
         given regex r"^full_quarter ← rotation_z\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.full_quarter = Rc::new(rotation_z(PI / denominator));
         };
 
         given "inv ← inverse(half_quarter)" |world, _step| {
-            world.inv = world.half_quarter.inverse();
+            world.inv = world.half_quarter.inverse().unwrap();
         };
 
         given regex r"^transform ← shearing\((.*), (.*), (.*), (.*), (.*), (.*)\)$" |world, matches, _step| {
-            let xy: f32 = matches[1].parse().unwrap();
-            let xz: f32 = matches[2].parse().unwrap();
-            let yx: f32 = matches[3].parse().unwrap();
-            let yz: f32 = matches[4].parse().unwrap();
-            let zx: f32 = matches[5].parse().unwrap();
-            let zy: f32 = matches[6].parse().unwrap();
+            let xy: Float = matches[1].parse().unwrap();
+            let xz: Float = matches[2].parse().unwrap();
+            let yx: Float = matches[3].parse().unwrap();
+            let yz: Float = matches[4].parse().unwrap();
+            let zx: Float = matches[5].parse().unwrap();
+            let zy: Float = matches[6].parse().unwrap();
 
             world.transform = Rc::new(shearing(xy, xz, yx, yz, zx, zy));
         };
 
         given regex r"^A ← rotation_x\(π / (.*)\)$" |world, matches, _step| {
-            let denominator: f32 = matches[1].parse().unwrap();
+            let denominator: Float = matches[1].parse().unwrap();
 
             world.matrix_a = Rc::new(AnyMatrix::M4x4(rotation_x(PI / denominator)));
         };
 
         given regex r"^B ← scaling\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.matrix_b = Rc::new(AnyMatrix::M4x4(scaling(x, y, z)));
         };
 
         given regex r"^C ← translation\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.matrix_c = Rc::new(AnyMatrix::M4x4(translation(x, y, z)));
         };
 
         given regex r"^t ← translation\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.t = Rc::new(translation(x, y, z));
         };
 
         given regex r"^origin ← point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.origin = Point::new(x, y, z);
         };
 
         given regex r"^direction ← vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.direction = Vector::new(x, y, z);
         };
 
         given regex r"^r ← ray\(point\((.*), (.*), (.*)\), vector\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let px: f32 = matches[1].parse().unwrap();
-            let py: f32 = matches[2].parse().unwrap();
-            let pz: f32 = matches[3].parse().unwrap();
+            let px: Float = matches[1].parse().unwrap();
+            let py: Float = matches[2].parse().unwrap();
+            let pz: Float = matches[3].parse().unwrap();
 
-            let vx: f32 = matches[4].parse().unwrap();
-            let vy: f32 = matches[5].parse().unwrap();
-            let vz: f32 = matches[6].parse().unwrap();
+            let vx: Float = matches[4].parse().unwrap();
+            let vy: Float = matches[5].parse().unwrap();
+            let vz: Float = matches[6].parse().unwrap();
 
             world.r = Ray::new(Point::new(px, py, pz), Vector::new(vx, vy, vz));
         };
@@ -604,56 +608,56 @@ mod example_steps {
         };
 
         given "s1 is added to w" |world, _step| {
-            world.rw.add_object(world.s1);
+            world.rw.add_object(world.s1.clone());
         };
 
         given "s2 is added to w" |world, _step| {
-            world.rw.add_object(world.s2);
+            world.rw.add_object(world.s2.clone());
         };
 
         given "p ← plane()" |world, _step| {
             world.plane = world.rw.new_plane();
-            world.rw.add_object(world.plane);
+            world.rw.add_object(world.plane.clone());
         };
 
         given regex r"^i1 ← intersection\((.*), s\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::new(world.s.clone());
+            let object = Arc::new(world.s.clone());
 
-            world.i1 = Some(Rc::new(Intersection { time, object }));
+            world.i1 = Some(Intersection { time, object });
         };
 
         given regex r"^i ← intersection\((.*), s2\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::new(world.s2.clone());
+            let object = Arc::new(world.s2.clone());
 
-            world.i = Some(Rc::new(Intersection { time, object }));
+            world.i = Some(Intersection { time, object });
         };
 
         given regex r"^i2 ← intersection\((.*), s\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::new(world.s.clone());
+            let object = Arc::new(world.s.clone());
 
-            world.i2 = Some(Rc::new(Intersection { time, object }));
+            world.i2 = Some(Intersection { time, object });
         };
 
         given regex r"^i3 ← intersection\((.*), s\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::new(world.s.clone());
+            let object = Arc::new(world.s.clone());
 
-            world.i3 = Some(Rc::new(Intersection { time, object }));
+            world.i3 = Some(Intersection { time, object });
         };
 
         given regex r"^i4 ← intersection\((.*), s\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::new(world.s.clone());
+            let object = Arc::new(world.s.clone());
 
-            world.i4 = Some(Rc::new(Intersection { time, object }));
+            world.i4 = Some(Intersection { time, object });
         };
 
         given "xs ← intersections(i2, i1)" |world, _step| {
@@ -695,31 +699,31 @@ mod example_steps {
         };
 
         given regex r"^set_transform\(s, translation\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
-            world.s.transform = translation(x, y, z);
+            world.s.set_transform(translation(x, y, z));
         };
 
         given "set_transform(s, m)" |world, _step| {
-            world.s.transform = *world.m;
+            world.s.set_transform(*world.m);
         };
 
         given regex r"^n ← vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -739,25 +743,25 @@ mod example_steps {
         };
 
         given regex r"^m.ambient ← (.*)$" |world, matches, _step| {
-            let new_value : f32 = matches[1].parse().unwrap();
+            let new_value : Float = matches[1].parse().unwrap();
 
             world.mt.ambient = new_value;
         };
 
         given regex r"^eyev ← vector\((.*), (.*), (.*)\)" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -765,23 +769,23 @@ mod example_steps {
         };
 
         given regex r"^normalv ← vector\((.*), (.*), (.*)\)" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.normalv = Vector::new(x, y, z);
         };
 
         given regex r"^light ← point_light\(point\((.*), (.*), (.*)\), color\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let position = Point::new(x, y, z);
 
-            let red: f32 = matches[4].parse().unwrap();
-            let green: f32 = matches[5].parse().unwrap();
-            let blue: f32 = matches[6].parse().unwrap();
+            let red: Float = matches[4].parse().unwrap();
+            let green: Float = matches[5].parse().unwrap();
+            let blue: Float = matches[6].parse().unwrap();
 
             let intensity = Color::new(red, green, blue);
 
@@ -789,15 +793,15 @@ mod example_steps {
         };
 
         given regex r"^w\.light ← point_light\(point\((.*), (.*), (.*)\), color\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let position = Point::new(x, y, z);
 
-            let red: f32 = matches[4].parse().unwrap();
-            let green: f32 = matches[5].parse().unwrap();
-            let blue: f32 = matches[6].parse().unwrap();
+            let red: Float = matches[4].parse().unwrap();
+            let green: Float = matches[5].parse().unwrap();
+            let blue: Float = matches[6].parse().unwrap();
 
             let intensity = Color::new(red, green, blue);
 
@@ -819,8 +823,8 @@ mod example_steps {
 
             let color = Color::new(r, g, b);
 
-            let diffuse: f32 = table.rows[0][1].parse().unwrap();
-            let specular: f32 = table.rows[1][1].parse().unwrap();
+            let diffuse: Float = table.rows[0][1].parse().unwrap();
+            let specular: Float = table.rows[1][1].parse().unwrap();
 
             world.s1.material.color = color;
             world.s1.material.diffuse = diffuse;
@@ -833,9 +837,9 @@ mod example_steps {
             let regex = Regex::new(r"^(translation|scaling)\((.*), (.*), (.*)\)$").unwrap();
             let captures = regex.captures(&table.rows[0][1]).unwrap();
             let transformation_type: &str = captures.get(1).unwrap().as_str();
-            let x: f32 = captures.get(2).unwrap().as_str().parse().unwrap();
-            let y: f32 = captures.get(3).unwrap().as_str().parse().unwrap();
-            let z: f32 = captures.get(4).unwrap().as_str().parse().unwrap();
+            let x: Float = captures.get(2).unwrap().as_str().parse().unwrap();
+            let y: Float = captures.get(3).unwrap().as_str().parse().unwrap();
+            let z: Float = captures.get(4).unwrap().as_str().parse().unwrap();
 
             let transform = match transformation_type {
                 "scaling" => scaling(x, y, z),
@@ -843,7 +847,7 @@ mod example_steps {
                 _ => unimplemented!("Missing support for transformation of type {}", transformation_type),
             };
 
-            world.s2.transform = transform;
+            world.s2.set_transform(transform);
         };
 
         given "shape ← sphere() with:" |world, step| {
@@ -852,9 +856,9 @@ mod example_steps {
             let regex = Regex::new(r"^(translation|scaling)\((.*), (.*), (.*)\)$").unwrap();
             let captures = regex.captures(&table.rows[0][1]).unwrap();
             let transformation_type: &str = captures.get(1).unwrap().as_str();
-            let x: f32 = captures.get(2).unwrap().as_str().parse().unwrap();
-            let y: f32 = captures.get(3).unwrap().as_str().parse().unwrap();
-            let z: f32 = captures.get(4).unwrap().as_str().parse().unwrap();
+            let x: Float = captures.get(2).unwrap().as_str().parse().unwrap();
+            let y: Float = captures.get(3).unwrap().as_str().parse().unwrap();
+            let z: Float = captures.get(4).unwrap().as_str().parse().unwrap();
 
             let transform = match transformation_type {
                 "scaling" => scaling(x, y, z),
@@ -863,9 +867,9 @@ mod example_steps {
             };
 
             let mut shape = world.rw.new_sphere(CENTER_ORIGIN);
-            shape.transform = transform;
+            shape.set_transform(transform);
 
-            world.shape = Rc::new(shape);
+            world.shape = Arc::new(shape);
         };
 
         given "w ← default_world()" |world, _step| {
@@ -873,23 +877,23 @@ mod example_steps {
         };
 
         given "shape ← sphere()" |world, _step| {
-            world.shape = Rc::new(world.rw.new_sphere(CENTER_ORIGIN));
+            world.shape = Arc::new(world.rw.new_sphere(CENTER_ORIGIN));
         };
 
         given regex r"^i ← intersection\((.*), shape\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::clone(&world.shape);
+            let object = Arc::clone(&world.shape);
 
-            world.i = Some(Rc::new(Intersection { time, object }));
+            world.i = Some(Intersection { time, object });
         };
 
         given "shape ← the first object in w" |world, _step| {
-            world.shape = Rc::clone(&world.rw.objects().first().unwrap());
+            world.shape = Arc::clone(world.rw.objects().first().unwrap());
         };
 
         given "shape ← the second object in w" |world, _step| {
-            world.shape = Rc::clone(&world.rw.objects().get(1).unwrap());
+            world.shape = Arc::clone(world.rw.objects().get(1).unwrap());
         };
 
         given "outer ← the first object in w" |_world, _step| {
@@ -911,25 +915,25 @@ mod example_steps {
         };
 
         given regex r"^from ← point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.from = Point::new(x, y, z);
         };
 
         given regex r"^to ← point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.to = Point::new(x, y, z);
         };
 
         given regex r"^up ← vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             world.up = Vector::new(x, y, z);
         };
@@ -947,15 +951,15 @@ mod example_steps {
         };
 
         given regex r"^c ← camera\((.*), (.*), π/2\)$" |world, matches, _step| {
-            let hsize: f32 = matches[1].parse().unwrap();
-            let vsize: f32 = matches[2].parse().unwrap();
-            let field_of_view: f32 = PI / 2.0;
+            let hsize: Float = matches[1].parse().unwrap();
+            let vsize: Float = matches[2].parse().unwrap();
+            let field_of_view: Float = PI / 2.0;
 
             world.camera = Camera::new(hsize, vsize, field_of_view);
         };
 
         given "c.transform ← view_transform(from, to, up)" |world, _step| {
-            world.camera.transform = view_transform(&world.from, &world.to, &world.up);
+            world.camera.set_transform(view_transform(&world.from, &world.to, &world.up));
         };
 
         given "in_shadow ← true" |world, _step| {
@@ -980,14 +984,33 @@ mod example_steps {
             world.matrix_t = Rc::new(AnyMatrix::M4x4(product));
         };
 
+        when "T ← identity_matrix.rotate_x(π / 2).scale(5, 5, 5).translate(10, 5, 7)" |world, _step| {
+            let chained = TransformationMatrix::identity()
+                .rotate_x(PI / 2.0)
+                .scale(5.0, 5.0, 5.0)
+                .translate(10.0, 5.0, 7.0);
+
+            world.matrix_t = Rc::new(AnyMatrix::M4x4(chained));
+        };
+
+        when regex r"^T ← rotation\(vector\((.*), (.*), (.*)\), 2π / 3\)$" |world, matches, _step| {
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
+
+            let rotated = rotation(&Vector::new(x, y, z), 2.0 * PI / 3.0);
+
+            world.matrix_t = Rc::new(AnyMatrix::M4x4(rotated));
+        };
+
         when "r ← ray(origin, direction)" |world, _step| {
             world.r = Ray::new(world.origin, world.direction);
         };
 
         when "xs ← intersect(s, r)" |world, _step| {
-            let sphere_rc: Rc<RaytracerObject> = Rc::new(world.s.clone());
+            let sphere_arc: Arc<RaytracerObject> = Arc::new(world.s.clone());
 
-            world.xs = intersect(sphere_rc, &world.r);
+            world.xs = intersect(sphere_arc, &world.r);
         };
 
         when "xs ← intersections(i1, i2)" |world, _step| {
@@ -1007,13 +1030,13 @@ mod example_steps {
         when regex r"^i ← intersection\((.*), s\)$" |world, matches, _step| {
             let time: Time = matches[1].parse().unwrap();
 
-            let object = Rc::new(world.s.clone());
+            let object = Arc::new(world.s.clone());
 
-            world.i = Some(Rc::new(Intersection { time, object }));
+            world.i = Some(Intersection { time, object });
         };
 
         when "i ← hit(xs)" |world, _step| {
-            world.i = world.xs.hit();
+            world.i = world.xs.hit().cloned();
         };
 
         when "r2 ← transform(r, m)" |world, _step| {
@@ -1022,42 +1045,54 @@ mod example_steps {
         };
 
         when "set_transform(s, t)" |world, _step| {
-            world.s.transform = world.t.as_ref().clone();
+            world.s.set_transform(world.t.as_ref().clone());
         };
 
         when regex r"^set_transform\(s, scaling\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
+
+            world.s.set_transform(scaling(x, y, z));
+        };
+
+        when regex r"^try set_transform\(s, scaling\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
+
+            world.transform_result = world.s.try_set_transform(scaling(x, y, z));
+        };
 
-            world.s.transform = scaling(x, y, z);
+        when "try c.transform ← view_transform(from, to, up)" |world, _step| {
+            world.transform_result = world.camera.try_set_transform(view_transform(&world.from, &world.to, &world.up));
         };
 
         when regex r"^set_transform\(s, translation\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
-            world.s.transform = translation(x, y, z);
+            world.s.set_transform(translation(x, y, z));
         };
 
         when regex r"^n ← normal_at\(s, point\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√3/3" => 3.0_f32.sqrt() / 3.0,
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√3/3" => (3.0 as Float).sqrt() / 3.0,
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√3/3" => 3.0_f32.sqrt() / 3.0,
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√3/3" => (3.0 as Float).sqrt() / 3.0,
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√3/3" => 3.0_f32.sqrt() / 3.0,
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√3/3" => (3.0 as Float).sqrt() / 3.0,
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1073,11 +1108,11 @@ mod example_steps {
         };
 
         when "m ← s.material" |world, _step| {
-            world.mt = world.s.material;
+            world.mt = world.s.material.clone();
         };
 
         when "s.material ← m" |world, _step| {
-            world.s.material = world.mt;
+            world.s.material = world.mt.clone();
         };
 
         when "result ← lighting(m, light, position, eyev, normalv)" |world, _step| {
@@ -1097,7 +1132,7 @@ mod example_steps {
         };
 
         when "xs ← local_intersect(p, r)" |world, _step| {
-            world.xs = local_intersect(Rc::new(world.plane), &world.r);
+            world.xs = local_intersect(Arc::new(world.plane.clone()), &world.r);
         };
 
         when "comps ← prepare_computations(i, r)" |world, _step| {
@@ -1128,7 +1163,7 @@ mod example_steps {
         };
 
         when "c.transform ← rotation_y(π/4) * translation(0, -2, 5)" |world, _step| {
-            world.camera.transform = rotation_y(PI/4.0) * translation(0.0, -2.0, 5.0);
+            world.camera.set_transform(rotation_y(PI/4.0) * translation(0.0, -2.0, 5.0));
         };
 
         when "image ← render(c, w)" |world, _step| {
@@ -1137,9 +1172,9 @@ mod example_steps {
 
         when regex r"^n(.*) ← local_normal_at\(p, point\((.*), (.*), (.*)\)\)$" |world, matches, _step| {
             let n_index: usize = matches[1].parse().unwrap();
-            let x: f32 = matches[2].parse().unwrap();
-            let y: f32 = matches[3].parse().unwrap();
-            let z: f32 = matches[4].parse().unwrap();
+            let x: Float = matches[2].parse().unwrap();
+            let y: Float = matches[3].parse().unwrap();
+            let z: Float = matches[4].parse().unwrap();
 
             let point = Point::new(x, y, z);
 
@@ -1160,9 +1195,9 @@ mod example_steps {
             let color_i2: usize = matches[2].parse().unwrap();
             let color2 = world.colors[color_i2];
 
-            let red: f32 = matches[3].parse().unwrap();
-            let green: f32 = matches[4].parse().unwrap();
-            let blue: f32 = matches[5].parse().unwrap();
+            let red: Float = matches[3].parse().unwrap();
+            let green: Float = matches[4].parse().unwrap();
+            let blue: Float = matches[5].parse().unwrap();
 
             let expected = Color::new(red, green, blue);
 
@@ -1178,10 +1213,10 @@ mod example_steps {
         };
 
         then regex r"^A \* b = tuple\((.*), (.*), (.*), (.*)\)$" |world, matches, _step| {
-            let t1: f32 = matches[1].parse().unwrap();
-            let t2: f32 = matches[2].parse().unwrap();
-            let t3: f32 = matches[3].parse().unwrap();
-            let t4: f32 = matches[4].parse().unwrap();
+            let t1: Float = matches[1].parse().unwrap();
+            let t2: Float = matches[2].parse().unwrap();
+            let t3: Float = matches[3].parse().unwrap();
+            let t4: Float = matches[4].parse().unwrap();
 
             let tuple_matrix = Matrix4x1::new([
                 world.tuple.0,
@@ -1244,7 +1279,7 @@ mod example_steps {
         };
 
         then regex r"^determinant\(A\) = (.*)$" |world, matches, _step| {
-            let expected: f32 = matches[1].parse().unwrap();
+            let expected: Float = matches[1].parse().unwrap();
 
             let actual = match *world.matrix_a {
                 AnyMatrix::M2x2(m) => m.determinant(),
@@ -1257,7 +1292,7 @@ mod example_steps {
         };
 
         then regex r"^determinant\(B\) = (.*)$" |world, matches, _step| {
-            let expected: f32 = matches[1].parse().unwrap();
+            let expected: Float = matches[1].parse().unwrap();
 
             let actual = match *world.matrix_b {
                 AnyMatrix::M2x2(m) => m.determinant(),
@@ -1273,7 +1308,7 @@ mod example_steps {
             let row_i: usize = matches[1].parse().unwrap();
             let col_i: usize = matches[2].parse().unwrap();
 
-            let expected: f32 = matches[3].parse().unwrap();
+            let expected: Float = matches[3].parse().unwrap();
 
             let actual = match *world.matrix_a {
                 AnyMatrix::M2x2(_m) => unimplemented!("need Matrix2x2#cofactor"),
@@ -1289,7 +1324,7 @@ mod example_steps {
             let row_i: usize = matches[1].parse().unwrap();
             let col_i: usize = matches[2].parse().unwrap();
 
-            let expected: f32 = matches[3].parse().unwrap();
+            let expected: Float = matches[3].parse().unwrap();
 
             let actual = match *world.matrix_a {
                 AnyMatrix::M2x2(_m) => unimplemented!("need Matrix2x2#cofactor"),
@@ -1331,21 +1366,37 @@ mod example_steps {
             assert!(!m4x4(&world.matrix_a).invertible());
         };
 
+        then "transform is not invertible" |world, _step| {
+            assert!(!world.transform.invertible());
+        };
+
+        then "inverse(transform) is nothing" |world, _step| {
+            assert_eq!(None, world.transform.inverse());
+        };
+
+        then "the transform is rejected" |world, _step| {
+            assert!(world.transform_result.is_err());
+        };
+
+        then "the transform is accepted" |world, _step| {
+            assert!(world.transform_result.is_ok());
+        };
+
         then regex r"^B\[(.*),(.*)\] = (.*)/(.*)$" |world, matches, _step| {
             let row_i: usize = matches[1].parse().unwrap();
             let col_i: usize = matches[2].parse().unwrap();
 
-            let numerator: f32 = matches[3].parse().unwrap();
-            let denominator: f32 = matches[4].parse().unwrap();
+            let numerator: Float = matches[3].parse().unwrap();
+            let denominator: Float = matches[4].parse().unwrap();
 
             let mb = match *world.matrix_b {
                 AnyMatrix::M4x4(m) => m,
                 _ => panic!("Expected world.matrix_b to be a Matrix4x4"),
             };
 
-            let expected: f32 = (numerator as f32) / (denominator as f32);
+            let expected: Float = (numerator as Float) / (denominator as Float);
 
-            let actual = mb[[row_i, col_i]] as f32;
+            let actual = mb[[row_i, col_i]] as Float;
 
             assert_eq!(expected, actual);
         };
@@ -1373,7 +1424,7 @@ mod example_steps {
 
             let expected: Matrix4x4 = table_to_matrix4x4(table);
 
-            assert_eq!(expected, ma.inverse().rounded());
+            assert_eq!(expected, ma.inverse().unwrap().rounded());
         };
 
         then "C * inverse(B) = A" |world, _step| {
@@ -1392,15 +1443,15 @@ mod example_steps {
 
             let expected = ma;
 
-            let actual = (mc * mb.inverse()).rounded();
+            let actual = (mc * mb.inverse().unwrap()).rounded();
 
             assert_eq!(expected, actual);
         };
 
         then regex r"^transform \* p = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1410,19 +1461,19 @@ mod example_steps {
         };
 
         then regex r"^inv \* p = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1442,9 +1493,9 @@ mod example_steps {
         };
 
         then regex r"^transform \* v = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Vector::new(x, y, z);
 
@@ -1454,9 +1505,9 @@ mod example_steps {
         };
 
         then regex r"^inv \* v = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Vector::new(x, y, z);
 
@@ -1466,19 +1517,19 @@ mod example_steps {
         };
 
         then regex r"^half_quarter \* p = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1486,13 +1537,13 @@ mod example_steps {
 
             let actual = world.half_quarter.as_ref() * world.p;
 
-            assert_eq!(expected, actual);
+            assert_eq!(expected.rounded(), actual.rounded());
         };
 
         then regex r"^full_quarter \* p = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1502,9 +1553,9 @@ mod example_steps {
         };
 
         then regex r"^p2 = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1514,9 +1565,9 @@ mod example_steps {
         };
 
         then regex r"^p3 = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1526,9 +1577,9 @@ mod example_steps {
         };
 
         then regex r"^p4 = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1538,9 +1589,9 @@ mod example_steps {
         };
 
         then regex r"^T \* p = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1556,9 +1607,9 @@ mod example_steps {
         };
 
         then regex r"^r.origin = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1566,19 +1617,19 @@ mod example_steps {
         };
 
         then regex r"^r.direction = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1588,9 +1639,9 @@ mod example_steps {
         };
 
         then regex r"^r2.origin = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1604,9 +1655,9 @@ mod example_steps {
         };
 
         then regex r"^r2.direction = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = Vector::new(x, y, z);
 
@@ -1616,10 +1667,10 @@ mod example_steps {
         };
 
         then regex r"^position\(r, (.*)\) = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let t: f32 = matches[1].parse().unwrap();
-            let x: f32 = matches[2].parse().unwrap();
-            let y: f32 = matches[3].parse().unwrap();
-            let z: f32 = matches[4].parse().unwrap();
+            let t: Float = matches[1].parse().unwrap();
+            let x: Float = matches[2].parse().unwrap();
+            let y: Float = matches[3].parse().unwrap();
+            let z: Float = matches[4].parse().unwrap();
 
             let expected = Point::new(x, y, z);
 
@@ -1753,7 +1804,7 @@ mod example_steps {
         then "s.transform = identity_matrix" |world, _step| {
             let expected = TransformationMatrix::identity();
 
-            let actual = world.s.transform;
+            let actual = world.s.transform();
 
             assert_eq!(expected, actual);
         };
@@ -1761,34 +1812,34 @@ mod example_steps {
         then "s.transform = t" |world, _step| {
             let expected = world.t.as_ref().clone();
 
-            let actual = world.s.transform.clone();
+            let actual = world.s.transform();
 
             assert_eq!(expected, actual);
         };
 
         then regex r"^s.transform = translation\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = matches[1].parse().unwrap();
-            let y: f32 = matches[2].parse().unwrap();
-            let z: f32 = matches[3].parse().unwrap();
+            let x: Float = matches[1].parse().unwrap();
+            let y: Float = matches[2].parse().unwrap();
+            let z: Float = matches[3].parse().unwrap();
 
             let expected = translation(x, y, z);
 
-            let actual = world.s.transform.clone();
+            let actual = world.s.transform();
 
             assert_eq!(expected, actual);
         };
 
         then regex r"^n = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√3/3" => 3.0_f32.sqrt() / 3.0,
+            let x: Float = match matches[1].as_str() {
+                "√3/3" => (3.0 as Float).sqrt() / 3.0,
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√3/3" => 3.0_f32.sqrt() / 3.0,
+            let y: Float = match matches[2].as_str() {
+                "√3/3" => (3.0 as Float).sqrt() / 3.0,
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√3/3" => 3.0_f32.sqrt() / 3.0,
+            let z: Float = match matches[3].as_str() {
+                "√3/3" => (3.0 as Float).sqrt() / 3.0,
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1808,19 +1859,19 @@ mod example_steps {
         };
 
         then regex r"^r = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1840,9 +1891,9 @@ mod example_steps {
         };
 
         then regex r"^m.color = color\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let r: f32 = matches[1].parse().unwrap();
-            let g: f32 = matches[2].parse().unwrap();
-            let b: f32 = matches[3].parse().unwrap();
+            let r: Float = matches[1].parse().unwrap();
+            let g: Float = matches[2].parse().unwrap();
+            let b: Float = matches[3].parse().unwrap();
 
             let expected = Color::new(r, g, b);
 
@@ -1852,7 +1903,7 @@ mod example_steps {
         };
 
         then regex r"^m.ambient = (.*)$" |world, matches, _step| {
-            let expected : f32 = matches[1].parse().unwrap();
+            let expected : Float = matches[1].parse().unwrap();
 
             let actual = world.mt.ambient;
 
@@ -1860,7 +1911,7 @@ mod example_steps {
         };
 
         then regex r"^m.diffuse = (.*)$" |world, matches, _step| {
-            let expected : f32 = matches[1].parse().unwrap();
+            let expected : Float = matches[1].parse().unwrap();
 
             let actual = world.mt.diffuse;
 
@@ -1868,7 +1919,7 @@ mod example_steps {
         };
 
         then regex r"^m.specular = (.*)$" |world, matches, _step| {
-            let expected : f32 = matches[1].parse().unwrap();
+            let expected : Float = matches[1].parse().unwrap();
 
             let actual = world.mt.specular;
 
@@ -1876,7 +1927,7 @@ mod example_steps {
         };
 
         then regex r"^m.shininess = (.*)$" |world, matches, _step| {
-            let expected : f32 = matches[1].parse().unwrap();
+            let expected : Float = matches[1].parse().unwrap();
 
             let actual = world.mt.shininess;
 
@@ -1886,34 +1937,34 @@ mod example_steps {
         then "m = material()" |world, _step| {
             let expected = Material::default();
 
-            let actual = world.s.material;
+            let actual = world.s.material.clone();
 
             assert_eq!(expected, actual);
         };
 
         then "s.material = m" |world, _step| {
-            let expected = world.s.material;
+            let expected = world.s.material.clone();
 
-            let actual = world.mt;
+            let actual = world.mt.clone();
 
             assert_eq!(expected, actual);
         };
 
         then regex r"^result = color\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let r: f32 = matches[1].parse().unwrap();
-            let g: f32 = matches[2].parse().unwrap();
-            let b: f32 = matches[3].parse().unwrap();
+            let r: Float = matches[1].parse().unwrap();
+            let g: Float = matches[2].parse().unwrap();
+            let b: Float = matches[3].parse().unwrap();
 
             let expected = Color::new(r, g, b);
 
             let actual = world.result;
 
-            assert_eq!(expected.rounded(), actual.rounded());
+            assert!(expected.equalish_to(&actual), "{:?} != {:?}", expected, actual);
         };
 
         then "w contains no objects" |world, _step| {
-            let expected: Vec<Rc<RaytracerObject>> = vec![];
-            let actual = world.rw.objects();
+            let expected: Vec<Arc<RaytracerObject>> = vec![];
+            let actual = world.rw.objects().to_vec();
 
             assert_eq!(expected, actual);
         };
@@ -1941,12 +1992,12 @@ mod example_steps {
                 _ => panic!("Unrecognized object name: {}", expected_obj_name),
             };
 
-            let equivalent_obj = |o: &&Rc<RaytracerObject>| -> bool {
+            let equivalent_obj = |o: &&Arc<RaytracerObject>| -> bool {
                 let obj: RaytracerObject = o.as_ref().clone();
 
                 obj.obj_type == expected.obj_type
                     && obj.origin == expected.origin
-                    && obj.transform == expected.transform
+                    && obj.transform() == expected.transform()
                     && obj.material == expected.material
             };
 
@@ -1970,19 +2021,19 @@ mod example_steps {
         };
 
         then regex r"^comps.point = point\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -1993,19 +2044,19 @@ mod example_steps {
         };
 
         then regex r"^comps.eyev = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -2016,19 +2067,19 @@ mod example_steps {
         };
 
         then regex r"^comps.normalv = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let x: f32 = match matches[1].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let x: Float = match matches[1].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[1].parse().unwrap(),
             };
-            let y: f32 = match matches[2].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let y: Float = match matches[2].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[2].parse().unwrap(),
             };
-            let z: f32 = match matches[3].as_str() {
-                "√2/2" => 2.0_f32.sqrt() / 2.0,
-                "-√2/2" => -(2.0_f32.sqrt() / 2.0),
+            let z: Float = match matches[3].as_str() {
+                "√2/2" => (2.0 as Float).sqrt() / 2.0,
+                "-√2/2" => -((2.0 as Float).sqrt() / 2.0),
                 _ => matches[3].parse().unwrap(),
             };
 
@@ -2047,7 +2098,7 @@ mod example_steps {
         };
 
         then "comps.over_point.z < -EPSILON/2" |world, _step| {
-            let expected: f32 = -(EPSILON/2.0);
+            let expected: Float = -(EPSILON/2.0);
 
             let actual = world.comps.as_ref().unwrap().over_point.z();
 
@@ -2061,9 +2112,9 @@ mod example_steps {
         };
 
         then regex r"^c = color\((.*), (.*), (.*)\)$" |world, matches, _step| {
-            let r: f32 = matches[1].parse().unwrap();
-            let g: f32 = matches[2].parse().unwrap();
-            let b: f32 = matches[3].parse().unwrap();
+            let r: Float = matches[1].parse().unwrap();
+            let g: Float = matches[2].parse().unwrap();
+            let b: Float = matches[3].parse().unwrap();
 
             let expected = Color::new(r, g, b);
 
@@ -2138,13 +2189,13 @@ mod example_steps {
         then "c.transform = identity_matrix" |world, _step| {
             let expected = &TransformationMatrix::identity();
 
-            let actual = &world.camera.transform;
+            let actual = &world.camera.transform();
 
             assert_eq!(expected, actual);
         };
 
         then "c.pixel_size = 0.01" |world, _step| {
-            assert_eq!(0.01_f32, world.camera.pixel_size);
+            assert!(equalish(0.01, world.camera.pixel_size));
         };
 
         then "pixel_at(image, 5, 5) = color(0.38066, 0.47583, 0.2855)" |world, _step| {
@@ -2165,9 +2216,9 @@ mod example_steps {
 
         then regex r"^n(.*) = vector\((.*), (.*), (.*)\)$" |world, matches, _step| {
             let n_index: usize = matches[1].parse().unwrap();
-            let x: f32 = matches[2].parse().unwrap();
-            let y: f32 = matches[3].parse().unwrap();
-            let z: f32 = matches[4].parse().unwrap();
+            let x: Float = matches[2].parse().unwrap();
+            let y: Float = matches[3].parse().unwrap();
+            let z: Float = matches[4].parse().unwrap();
 
             let expected = match n_index {
                 1 => world.n1,
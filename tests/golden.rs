@@ -0,0 +1,102 @@
+//! Renders the built-in scenes at a small size and compares them against reference images in
+//! tests/golden. Set UPDATE_GOLDEN=1 to rewrite the references after an intended change to
+//! shading, and look them over before committing. See tests/golden/README.md for what the
+//! single precision references do and don't show.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use ray_tracer_challenge::canvas::Canvas;
+use ray_tracer_challenge::scenes;
+use ray_tracer_challenge::Float;
+
+const WIDTH: Float = 64.0;
+const HEIGHT: Float = 32.0;
+
+// Two 8-bit steps absorb rounding differences between platforms
+const CHANNEL_THRESHOLD: Float = 2.0 / 255.0;
+const MAX_PIXELS_OVER_THRESHOLD: usize = 10;
+const MAX_RMSE: Float = 0.005;
+
+// Double precision renders differ visibly, mostly by missing f32's surface acne, so they get
+// references of their own
+fn reference_path(name: &str) -> PathBuf {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden");
+
+    let directory = if cfg!(feature = "f64") {
+        directory.join("f64")
+    } else {
+        directory
+    };
+
+    directory.join(format!("{}.ppm", name))
+}
+
+fn save(canvas: &Canvas, path: &PathBuf) {
+    let file = File::create(path).unwrap();
+    canvas.write_p6(BufWriter::new(file)).unwrap();
+}
+
+// Rendered at 8 bits per channel, the same as the stored reference
+fn render(name: &str) -> Canvas {
+    let scene = scenes::by_name(name, WIDTH, HEIGHT).unwrap();
+    let image = scene.camera.render(&scene.world);
+
+    let mut bytes = vec![];
+    image.write_p6(&mut bytes).unwrap();
+
+    Canvas::from_ppm(bytes).unwrap()
+}
+
+fn check_against_reference(name: &str) {
+    let actual = render(name);
+    let path = reference_path(name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        save(&actual, &path);
+        return;
+    }
+
+    let expected = Canvas::from_ppm(fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "Missing reference {:?} ({}). Run with UPDATE_GOLDEN=1 to create it.",
+            path, e
+        )
+    }))
+    .unwrap();
+
+    let diff = actual.diff(&expected, CHANNEL_THRESHOLD);
+
+    if diff.pixels_over_threshold > MAX_PIXELS_OVER_THRESHOLD || diff.rmse > MAX_RMSE {
+        let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        let actual_path = output.join(format!("{}_actual.ppm", name));
+        let diff_path = output.join(format!("{}_diff.ppm", name));
+
+        save(&actual, &actual_path);
+        save(&diff.false_color(), &diff_path);
+
+        panic!(
+            "{} differs from its reference: {} pixels over threshold, RMSE {:.5}, PSNR {:.2} dB, \
+             max error {:.4}. See {:?} and {:?}.",
+            name,
+            diff.pixels_over_threshold,
+            diff.rmse,
+            diff.psnr,
+            diff.max_error,
+            actual_path,
+            diff_path
+        );
+    }
+}
+
+// Every built-in scene, so a new one can't be added without a reference
+#[test]
+fn every_scene_matches_its_reference() {
+    for name in scenes::SCENE_NAMES.iter() {
+        check_against_reference(name);
+    }
+}